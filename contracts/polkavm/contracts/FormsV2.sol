@@ -21,6 +21,29 @@ contract FormsV2 {
     /// @notice Total number of forms
     uint256 public formCount;
 
+    /// @notice Allowlisted respondents: formId => respondent => allowed
+    mapping(uint256 => mapping(address => bool)) public allowlist;
+
+    /// @notice Whether a form only accepts allowlisted respondents
+    mapping(uint256 => bool) public allowlistEnabled;
+
+    /// @dev Submitter of each response: formId => responseIdx => respondent
+    mapping(uint256 => mapping(uint256 => address)) internal responseRespondents;
+
+    /// @dev Submission time of each response: formId => responseIdx => timestamp
+    mapping(uint256 => mapping(uint256 => uint256)) internal responseTimestamps;
+
+    /// @dev Budget for `getResponses` return data, matching the PolkaVM build
+    uint256 private constant MAX_PAGE_BYTES = 8 * 1024;
+
+    /// @notice One entry of `getResponses`
+    struct Response {
+        uint256 idx;
+        address respondent;
+        uint256 timestamp;
+        string cid;
+    }
+
     // ========== EVENTS ==========
 
     event FormRegistered(
@@ -37,6 +60,12 @@ contract FormsV2 {
         uint256 timestamp
     );
 
+    event AllowlistUpdated(
+        uint256 indexed formId,
+        address indexed respondent,
+        bool allowed
+    );
+
     // ========== CORE FUNCTIONS ==========
 
     /**
@@ -65,15 +94,69 @@ contract FormsV2 {
     function submitResponse(uint256 formId, string memory responseCid) external returns (uint256) {
         require(bytes(formCids[formId]).length > 0, "Form does not exist");
         require(bytes(responseCid).length > 0, "Invalid CID");
+        require(isEligible(formId, msg.sender), "Not eligible");
 
         responseCids[formId].push(responseCid);
         uint256 responseIdx = responseCids[formId].length - 1;
+        responseRespondents[formId][responseIdx] = msg.sender;
+        responseTimestamps[formId][responseIdx] = block.timestamp;
 
         emit ResponseSubmitted(formId, responseIdx, responseCid, block.timestamp);
 
         return responseIdx;
     }
 
+    // ========== ALLOWLIST ==========
+
+    /**
+     * @notice Restrict a form to allowlisted respondents and add `respondents`;
+     *         an empty list leaves the form as it is
+     * @param formId The form ID
+     * @param respondents Addresses allowed to respond
+     */
+    function addToAllowlist(uint256 formId, address[] calldata respondents) external {
+        _requireFormCreator(formId);
+        if (respondents.length > 0) {
+            allowlistEnabled[formId] = true;
+        }
+        for (uint256 i = 0; i < respondents.length; i++) {
+            if (!allowlist[formId][respondents[i]]) {
+                allowlist[formId][respondents[i]] = true;
+                emit AllowlistUpdated(formId, respondents[i], true);
+            }
+        }
+    }
+
+    /**
+     * @notice Remove respondents; the form stays restricted even if none are left
+     * @param formId The form ID
+     * @param respondents Addresses no longer allowed to respond
+     */
+    function removeFromAllowlist(uint256 formId, address[] calldata respondents) external {
+        _requireFormCreator(formId);
+        for (uint256 i = 0; i < respondents.length; i++) {
+            if (allowlist[formId][respondents[i]]) {
+                allowlist[formId][respondents[i]] = false;
+                emit AllowlistUpdated(formId, respondents[i], false);
+            }
+        }
+    }
+
+    /**
+     * @notice Check whether an address may respond to a form
+     * @param formId The form ID
+     * @param respondent The address to check
+     * @return True if the form is unrestricted or the address is allowlisted
+     */
+    function isEligible(uint256 formId, address respondent) public view returns (bool) {
+        return !allowlistEnabled[formId] || allowlist[formId][respondent];
+    }
+
+    function _requireFormCreator(uint256 formId) internal view {
+        require(bytes(formCids[formId]).length > 0, "Form does not exist");
+        require(formCreators[formId] == msg.sender, "Not form creator");
+    }
+
     // ========== VIEW FUNCTIONS ==========
 
     /**
@@ -86,12 +169,68 @@ contract FormsV2 {
     }
 
     /**
-     * @notice Get all response CIDs for a form
+     * @notice Get the first response CIDs for a form
+     * @dev Stops once the return data would exceed MAX_PAGE_BYTES; page
+     *      through the rest with getResponses
      * @param formId The form ID
-     * @return Array of CID strings
+     * @return cids Array of CID strings
      */
-    function getResponseCids(uint256 formId) external view returns (string[] memory) {
-        return responseCids[formId];
+    function getResponseCids(uint256 formId) external view returns (string[] memory cids) {
+        string[] storage all = responseCids[formId];
+        uint256 size = 64; // offset and length of the array
+        uint256 count = 0;
+        for (; count < all.length; count++) {
+            // Entry offset, cid length and padded cid
+            size += 2 * 32 + ((bytes(all[count]).length + 31) / 32) * 32;
+            if (size > MAX_PAGE_BYTES && count > 0) {
+                break;
+            }
+        }
+
+        cids = new string[](count);
+        for (uint256 i = 0; i < count; i++) {
+            cids[i] = all[i];
+        }
+    }
+
+    /**
+     * @notice Get a page of responses with their respondents and timestamps
+     * @dev A page also ends once its return data would exceed MAX_PAGE_BYTES,
+     *      so keep paging from offset + page length until getResponseCount
+     * @param formId The form ID
+     * @param offset Index of the first response
+     * @param limit Maximum number of responses
+     * @return page Responses from offset on, empty past the end
+     */
+    function getResponses(uint256 formId, uint256 offset, uint256 limit)
+        external
+        view
+        returns (Response[] memory page)
+    {
+        string[] storage cids = responseCids[formId];
+        if (offset >= cids.length || limit == 0) {
+            return new Response[](0);
+        }
+        uint256 end = limit < cids.length - offset ? offset + limit : cids.length;
+
+        // Count the entries that fit first, so the page is allocated once
+        uint256 size = 64; // offset and length of the array
+        uint256 count = 0;
+        for (uint256 idx = offset; idx < end; idx++) {
+            // Entry offset, tuple head, cid length and padded cid
+            size += 6 * 32 + ((bytes(cids[idx]).length + 31) / 32) * 32;
+            // Always return at least one response so paging makes progress
+            if (size > MAX_PAGE_BYTES && count > 0) {
+                break;
+            }
+            count++;
+        }
+
+        page = new Response[](count);
+        for (uint256 i = 0; i < count; i++) {
+            uint256 idx = offset + i;
+            page[i] = Response(idx, responseRespondents[formId][idx], responseTimestamps[formId][idx], cids[idx]);
+        }
     }
 
     /**
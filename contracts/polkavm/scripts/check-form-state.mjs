@@ -25,9 +25,9 @@ const paseoChain = {
 
 const FORMS_ABI = [
   { name: 'formCount', type: 'function', inputs: [], outputs: [{ name: '', type: 'uint256' }], stateMutability: 'view' },
-  { name: 'responseCount', type: 'function', inputs: [{ name: 'formId', type: 'uint64' }], outputs: [{ name: 'count', type: 'uint256' }], stateMutability: 'view' },
-  { name: 'getFormCid', type: 'function', inputs: [{ name: 'formId', type: 'uint64' }], outputs: [{ name: 'cid', type: 'bytes' }], stateMutability: 'view' },
-  { name: 'getResponseCid', type: 'function', inputs: [{ name: 'formId', type: 'uint64' }, { name: 'idx', type: 'uint64' }], outputs: [{ name: 'cid', type: 'bytes' }], stateMutability: 'view' },
+  { name: 'getResponseCount', type: 'function', inputs: [{ name: 'formId', type: 'uint256' }], outputs: [{ name: '', type: 'uint256' }], stateMutability: 'view' },
+  { name: 'getFormCid', type: 'function', inputs: [{ name: 'formId', type: 'uint256' }], outputs: [{ name: '', type: 'string' }], stateMutability: 'view' },
+  { name: 'getResponseCids', type: 'function', inputs: [{ name: 'formId', type: 'uint256' }], outputs: [{ name: '', type: 'string[]' }], stateMutability: 'view' },
 ];
 
 const publicClient = createPublicClient({ chain: paseoChain, transport: http(EVM_RPC) });
@@ -54,32 +54,24 @@ for (let i = 1; i <= Number(formCount); i++) {
     functionName: 'getFormCid',
     args: [BigInt(i)],
   });
-
-  const cidHex = formCid.startsWith('0x') ? formCid.slice(2) : formCid;
-  const formCidStr = cidHex ? Buffer.from(cidHex, 'hex').toString('utf8') : '(empty)';
-  console.log('  Form CID:', formCidStr);
+  console.log('  Form CID:', formCid || '(empty)');
 
   const responseCount = await publicClient.readContract({
     address: contractAddress,
     abi: FORMS_ABI,
-    functionName: 'responseCount',
+    functionName: 'getResponseCount',
     args: [BigInt(i)],
   });
   console.log('  Responses:', responseCount.toString());
 
   // Fetch response CIDs
-  for (let j = 0; j < Number(responseCount); j++) {
-    const responseCid = await publicClient.readContract({
-      address: contractAddress,
-      abi: FORMS_ABI,
-      functionName: 'getResponseCid',
-      args: [BigInt(i), BigInt(j)],
-    });
-
-    const respCidHex = responseCid.startsWith('0x') ? responseCid.slice(2) : responseCid;
-    const respCidStr = respCidHex ? Buffer.from(respCidHex, 'hex').toString('utf8') : '(empty)';
-    console.log(`    Response[${j}]:`, respCidStr);
-  }
+  const responseCids = await publicClient.readContract({
+    address: contractAddress,
+    abi: FORMS_ABI,
+    functionName: 'getResponseCids',
+    args: [BigInt(i)],
+  });
+  responseCids.forEach((cid, j) => console.log(`    Response[${j}]:`, cid));
 }
 
 console.log('');
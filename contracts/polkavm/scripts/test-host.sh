@@ -0,0 +1,14 @@
+#!/bin/bash
+set -e
+
+# Host-side tests run against the in-memory mock of the pallet-revive host
+# functions (`mock` feature). Cargo picks up .cargo/config.toml and
+# rust-toolchain.toml from the working directory, so run from the parent
+# directory to build for the host instead of PolkaVM.
+cd "$(dirname "$0")/../.."
+
+echo "Running host-side contract tests..."
+cargo test --manifest-path polkavm/Cargo.toml --features mock \
+  --lib --bin accesspass --bin forms --bin forms_v2 "$@"
+
+echo "✅ Host tests complete"
@@ -1,6 +1,6 @@
 #!/usr/bin/env node
 /**
- * Test getResponseCount and getResponseCid on deployed contract
+ * Test getResponseCount and responseCids on deployed contract
  */
 
 import { createPublicClient, http, encodeFunctionData, decodeFunctionResult } from 'viem';
@@ -25,27 +25,27 @@ const paseoChain = {
 
 const ABI = [
   {
-    name: 'responseCount',
+    name: 'getResponseCount',
     type: 'function',
-    inputs: [{ name: 'formId', type: 'uint64' }],
-    outputs: [{ name: 'count', type: 'uint256' }],
+    inputs: [{ name: 'formId', type: 'uint256' }],
+    outputs: [{ name: '', type: 'uint256' }],
     stateMutability: 'view',
   },
   {
-    name: 'getResponseCid',
+    name: 'responseCids',
     type: 'function',
     inputs: [
-      { name: 'formId', type: 'uint64' },
-      { name: 'idx', type: 'uint64' },
+      { name: '', type: 'uint256' },
+      { name: '', type: 'uint256' },
     ],
-    outputs: [{ name: 'cid', type: 'bytes' }],
+    outputs: [{ name: '', type: 'string' }],
     stateMutability: 'view',
   },
   {
     name: 'getFormCid',
     type: 'function',
-    inputs: [{ name: 'formId', type: 'uint64' }],
-    outputs: [{ name: 'cid', type: 'bytes' }],
+    inputs: [{ name: 'formId', type: 'uint256' }],
+    outputs: [{ name: '', type: 'string' }],
     stateMutability: 'view',
   },
 ];
@@ -63,21 +63,17 @@ const formCidData = encodeFunctionData({
   args: [1n],
 });
 const formCidResult = await client.call({ to: contractAddress, data: formCidData });
-const formCidHex = decodeFunctionResult({ abi: ABI, functionName: 'getFormCid', data: formCidResult.data });
-const formCidBytes = formCidHex.startsWith('0x') ? formCidHex.slice(2) : formCidHex;
-const formCid = formCidBytes ? new TextDecoder().decode(
-  new Uint8Array(formCidBytes.match(/.{2}/g).map(b => parseInt(b, 16)))
-) : '';
+const formCid = decodeFunctionResult({ abi: ABI, functionName: 'getFormCid', data: formCidResult.data });
 console.log('✅ Form CID:', formCid || '(empty)');
 
 // Test 2: Get response count
 const countData = encodeFunctionData({
   abi: ABI,
-  functionName: 'responseCount',
+  functionName: 'getResponseCount',
   args: [1n],
 });
 const countResult = await client.call({ to: contractAddress, data: countData });
-const count = decodeFunctionResult({ abi: ABI, functionName: 'responseCount', data: countResult.data });
+const count = decodeFunctionResult({ abi: ABI, functionName: 'getResponseCount', data: countResult.data });
 
 console.log('✅ Response count for form 1:', count.toString());
 
@@ -86,15 +82,11 @@ if (count > 0n) {
   for (let i = 0; i < Number(count); i++) {
     const cidData = encodeFunctionData({
       abi: ABI,
-      functionName: 'getResponseCid',
+      functionName: 'responseCids',
       args: [1n, BigInt(i)],
     });
     const cidResult = await client.call({ to: contractAddress, data: cidData });
-    const cidHex = decodeFunctionResult({ abi: ABI, functionName: 'getResponseCid', data: cidResult.data });
-    const hexBody = cidHex.startsWith('0x') ? cidHex.slice(2) : cidHex;
-    const cid = hexBody ? new TextDecoder().decode(
-      new Uint8Array(hexBody.match(/.{2}/g).map(b => parseInt(b, 16)))
-    ) : '';
+    const cid = decodeFunctionResult({ abi: ABI, functionName: 'responseCids', data: cidResult.data });
     console.log(`  Response[${i}]:`, cid);
   }
 } else {
@@ -1,6 +1,10 @@
-use ethabi::{decode, encode, ParamType, Token};
+use alloc::string::String;
+use alloc::vec::Vec;
+use ethabi::{decode, ParamType, Token, Uint as U256};
 
-/// Helper functions for ABI encoding/decoding
+use crate::keccak::keccak256;
+
+// Helper functions for ABI encoding/decoding
 
 pub fn decode_call_data(
     types: &[ParamType],
@@ -13,3 +17,594 @@ pub fn decode_call_data(
 pub fn encode_return_value(tokens: &[Token]) -> alloc::vec::Vec<u8> {
     encode(tokens)
 }
+
+// ============ Selectors ============
+
+/// Function selector: first 4 bytes of keccak256 of the canonical signature,
+/// e.g. `selector("formCount()")`. Use in a `const` to compute at build time.
+pub const fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+// ============ Return Data ============
+
+/// Budget for the return data of a paged view such as `getResponses`; a full
+/// page peaks under 32 KiB of heap, as `mock::assert_full_page` checks
+pub const MAX_PAGE_BYTES: usize = 8 * 1024;
+
+// ============ Encoding ============
+
+/// ABI-encode `tokens`; same output as `ethabi::encode`.
+///
+/// `ethabi::encode` reserves a 32-byte word per output byte and collects the
+/// result from an iterator of unknown length, so under a bump allocator it
+/// costs over 30 times the size of its output. This version allocates the
+/// output once, at its exact size.
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(encoded_len(tokens));
+    encode_into(&mut out, tokens);
+    out
+}
+
+fn encoded_len(tokens: &[Token]) -> usize {
+    tokens.iter().map(|token| if token.is_dynamic() { 32 + token_len(token) } else { token_len(token) }).sum()
+}
+
+/// Encoded size of a token, excluding the offset word of a dynamic token.
+fn token_len(token: &Token) -> usize {
+    match token {
+        Token::Address(_) | Token::Int(_) | Token::Uint(_) | Token::Bool(_) => 32,
+        Token::FixedBytes(bytes) => padded_len(bytes.len()),
+        Token::Bytes(bytes) => 32 + padded_len(bytes.len()),
+        Token::String(s) => 32 + padded_len(s.len()),
+        Token::Array(items) => 32 + encoded_len(items),
+        Token::FixedArray(items) | Token::Tuple(items) => encoded_len(items),
+    }
+}
+
+fn padded_len(len: usize) -> usize {
+    len.div_ceil(32) * 32
+}
+
+/// Heads of `tokens` followed by the tails of the dynamic ones.
+fn encode_into(out: &mut Vec<u8>, tokens: &[Token]) {
+    let mut offset: usize = tokens.iter().map(|token| if token.is_dynamic() { 32 } else { token_len(token) }).sum();
+    for token in tokens {
+        if token.is_dynamic() {
+            push_word(out, U256::from(offset));
+            offset += token_len(token);
+        } else {
+            encode_token(out, token);
+        }
+    }
+    for token in tokens.iter().filter(|token| token.is_dynamic()) {
+        encode_token(out, token);
+    }
+}
+
+fn encode_token(out: &mut Vec<u8>, token: &Token) {
+    match token {
+        Token::Address(address) => {
+            out.extend_from_slice(&[0u8; 12]);
+            out.extend_from_slice(address.as_bytes());
+        }
+        Token::Int(value) | Token::Uint(value) => push_word(out, *value),
+        Token::Bool(value) => push_word(out, U256::from(*value as u8)),
+        Token::FixedBytes(bytes) => push_padded(out, bytes),
+        Token::Bytes(bytes) => {
+            push_word(out, U256::from(bytes.len()));
+            push_padded(out, bytes);
+        }
+        Token::String(s) => {
+            push_word(out, U256::from(s.len()));
+            push_padded(out, s.as_bytes());
+        }
+        Token::Array(items) => {
+            push_word(out, U256::from(items.len()));
+            encode_into(out, items);
+        }
+        Token::FixedArray(items) | Token::Tuple(items) => encode_into(out, items),
+    }
+}
+
+fn push_word(out: &mut Vec<u8>, value: U256) {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    out.extend_from_slice(&word);
+}
+
+fn push_padded(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + padded_len(bytes.len()) - bytes.len(), 0);
+}
+
+// ============ Solidity Types ============
+
+/// Canonical Solidity type name, as it appears in a function signature.
+pub enum SolName {
+    Elementary(&'static str),
+    Array { element: &'static SolName },
+    Tuple { fields: &'static [SolName] },
+}
+
+/// A Rust type with a fixed Solidity ABI representation.
+pub trait SolType: Sized {
+    const SOL_NAME: SolName;
+
+    fn param_type() -> ParamType;
+
+    /// Convert a decoded token, rejecting values out of range for `Self`.
+    fn from_token(token: Token) -> Option<Self>;
+
+    fn into_token(self) -> Token;
+}
+
+/// Marker for types usable as elements of a dynamic array (`T[]`).
+///
+/// `u8` is deliberately excluded so `Vec<u8>` keeps mapping to `bytes`.
+pub trait ArrayElement: SolType {}
+
+impl SolType for bool {
+    const SOL_NAME: SolName = SolName::Elementary("bool");
+
+    fn param_type() -> ParamType {
+        ParamType::Bool
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::Bool(self)
+    }
+}
+
+macro_rules! impl_sol_uint {
+    ($($ty:ty => $bits:literal, $name:literal;)*) => {$(
+        impl SolType for $ty {
+            const SOL_NAME: SolName = SolName::Elementary($name);
+
+            fn param_type() -> ParamType {
+                ParamType::Uint($bits)
+            }
+
+            fn from_token(token: Token) -> Option<Self> {
+                match token {
+                    Token::Uint(u) if u.bits() <= $bits => Some(u.low_u64() as $ty),
+                    _ => None,
+                }
+            }
+
+            fn into_token(self) -> Token {
+                Token::Uint(U256::from(self))
+            }
+        }
+    )*};
+}
+
+impl_sol_uint! {
+    u8 => 8, "uint8";
+    u32 => 32, "uint32";
+    u64 => 64, "uint64";
+}
+
+impl SolType for U256 {
+    const SOL_NAME: SolName = SolName::Elementary("uint256");
+
+    fn param_type() -> ParamType {
+        ParamType::Uint(256)
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Uint(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::Uint(self)
+    }
+}
+
+/// `address` as a raw 20-byte H160.
+impl SolType for [u8; 20] {
+    const SOL_NAME: SolName = SolName::Elementary("address");
+
+    fn param_type() -> ParamType {
+        ParamType::Address
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Address(addr) => Some(addr.0),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::Address(self.into())
+    }
+}
+
+impl SolType for [u8; 4] {
+    const SOL_NAME: SolName = SolName::Elementary("bytes4");
+
+    fn param_type() -> ParamType {
+        ParamType::FixedBytes(4)
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::FixedBytes(b) if b.len() == 4 => Some([b[0], b[1], b[2], b[3]]),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::FixedBytes(self.to_vec())
+    }
+}
+
+impl SolType for [u8; 32] {
+    const SOL_NAME: SolName = SolName::Elementary("bytes32");
+
+    fn param_type() -> ParamType {
+        ParamType::FixedBytes(32)
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::FixedBytes(b) if b.len() == 32 => {
+                let mut out = [0u8; 32];
+                out.copy_from_slice(&b);
+                Some(out)
+            }
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::FixedBytes(self.to_vec())
+    }
+}
+
+/// `bytes`
+impl SolType for Vec<u8> {
+    const SOL_NAME: SolName = SolName::Elementary("bytes");
+
+    fn param_type() -> ParamType {
+        ParamType::Bytes
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::Bytes(self)
+    }
+}
+
+impl SolType for String {
+    const SOL_NAME: SolName = SolName::Elementary("string");
+
+    fn param_type() -> ParamType {
+        ParamType::String
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::String(self)
+    }
+}
+
+/// `T[]`
+impl<T: ArrayElement> SolType for Vec<T> {
+    const SOL_NAME: SolName = SolName::Array { element: &T::SOL_NAME };
+
+    fn param_type() -> ParamType {
+        ParamType::Array(alloc::boxed::Box::new(T::param_type()))
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Array(items) => items.into_iter().map(T::from_token).collect(),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::Array(self.into_iter().map(T::into_token).collect())
+    }
+}
+
+/// `uint8[]`, kept apart from `Vec<u8>` (which is `bytes`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Uint8Array(pub Vec<u8>);
+
+impl SolType for Uint8Array {
+    const SOL_NAME: SolName = SolName::Array { element: &u8::SOL_NAME };
+
+    fn param_type() -> ParamType {
+        ParamType::Array(alloc::boxed::Box::new(u8::param_type()))
+    }
+
+    fn from_token(token: Token) -> Option<Self> {
+        match token {
+            Token::Array(items) => items.into_iter().map(u8::from_token).collect::<Option<_>>().map(Uint8Array),
+            _ => None,
+        }
+    }
+
+    fn into_token(self) -> Token {
+        Token::Array(self.0.into_iter().map(u8::into_token).collect())
+    }
+}
+
+impl ArrayElement for bool {}
+impl ArrayElement for u32 {}
+impl ArrayElement for u64 {}
+impl ArrayElement for U256 {}
+impl ArrayElement for [u8; 20] {}
+impl ArrayElement for [u8; 32] {}
+impl ArrayElement for String {}
+
+macro_rules! impl_sol_tuple {
+    ($($name:ident),+) => {
+        /// Tuple / struct (`(T1,T2,...)`)
+        impl<$($name: SolType),+> SolType for ($($name,)+) {
+            const SOL_NAME: SolName = SolName::Tuple { fields: &[$($name::SOL_NAME),+] };
+
+            fn param_type() -> ParamType {
+                ParamType::Tuple(alloc::vec![$($name::param_type()),+])
+            }
+
+            #[allow(non_snake_case)]
+            fn from_token(token: Token) -> Option<Self> {
+                match token {
+                    Token::Tuple(items) => {
+                        let mut items = items.into_iter();
+                        $(let $name = $name::from_token(items.next()?)?;)+
+                        Some(($($name,)+))
+                    }
+                    _ => None,
+                }
+            }
+
+            #[allow(non_snake_case)]
+            fn into_token(self) -> Token {
+                let ($($name,)+) = self;
+                Token::Tuple(alloc::vec![$($name.into_token()),+])
+            }
+        }
+
+        impl<$($name: SolType),+> ArrayElement for ($($name,)+) {}
+    };
+}
+
+impl_sol_tuple!(A);
+impl_sol_tuple!(A, B);
+impl_sol_tuple!(A, B, C);
+impl_sol_tuple!(A, B, C, D);
+impl_sol_tuple!(A, B, C, D, E);
+impl_sol_tuple!(A, B, C, D, E, F);
+impl_sol_tuple!(A, B, C, D, E, F, G);
+impl_sol_tuple!(A, B, C, D, E, F, G, H);
+impl_sol_tuple!(A, B, C, D, E, F, G, H, I);
+impl_sol_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_sol_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_sol_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+// ============ Signature Checking ============
+
+/// Match `name` against `sig` starting at `pos`, returning the position
+/// just past it.
+const fn match_name(sig: &[u8], pos: usize, name: &SolName) -> Option<usize> {
+    match name {
+        SolName::Elementary(elementary) => {
+            let bytes = elementary.as_bytes();
+            if pos + bytes.len() > sig.len() {
+                return None;
+            }
+            let mut i = 0;
+            while i < bytes.len() {
+                if sig[pos + i] != bytes[i] {
+                    return None;
+                }
+                i += 1;
+            }
+            Some(pos + bytes.len())
+        }
+        SolName::Array { element } => match match_name(sig, pos, element) {
+            Some(end) if end + 2 <= sig.len() && sig[end] == b'[' && sig[end + 1] == b']' => {
+                Some(end + 2)
+            }
+            _ => None,
+        },
+        SolName::Tuple { fields } => {
+            if pos >= sig.len() || sig[pos] != b'(' {
+                return None;
+            }
+            match match_list(sig, pos + 1, fields) {
+                Some(end) if end < sig.len() && sig[end] == b')' => Some(end + 1),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Match a comma-separated list of `names` starting at `pos`.
+const fn match_list(sig: &[u8], mut pos: usize, names: &[SolName]) -> Option<usize> {
+    let mut i = 0;
+    while i < names.len() {
+        if i > 0 {
+            if pos >= sig.len() || sig[pos] != b',' {
+                return None;
+            }
+            pos += 1;
+        }
+        pos = match match_name(sig, pos, &names[i]) {
+            Some(end) => end,
+            None => return None,
+        };
+        i += 1;
+    }
+    Some(pos)
+}
+
+/// Whether the parameter list of `signature` is exactly `args`, e.g.
+/// `"closeForm(uint64)"` against `[u64::SOL_NAME]`.
+pub const fn signature_matches(signature: &str, args: &[SolName]) -> bool {
+    let sig = signature.as_bytes();
+    let mut open = 0;
+    while open < sig.len() && sig[open] != b'(' {
+        open += 1;
+    }
+    if open == 0 || open == sig.len() {
+        return false;
+    }
+    match match_list(sig, open + 1, args) {
+        Some(end) => end + 1 == sig.len() && sig[end] == b')',
+        None => false,
+    }
+}
+
+// ============ Dispatch ============
+
+/// Why the generated dispatcher could not route a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchError {
+    UnknownSelector,
+    Decode,
+}
+
+/// Result of the generated dispatcher: ABI-encoded return data.
+pub type DispatchResult = Result<Vec<u8>, DispatchError>;
+
+/// Return value of a dispatched handler, ABI-encoded as return data.
+pub trait AbiReturn {
+    fn encode_return(self) -> Vec<u8>;
+}
+
+impl AbiReturn for () {
+    fn encode_return(self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+impl<T: SolType> AbiReturn for T {
+    fn encode_return(self) -> Vec<u8> {
+        encode(&[self.into_token()])
+    }
+}
+
+/// Generate a selector dispatcher from Solidity signatures.
+///
+/// Each entry names an existing handler function and its argument types.
+/// The selector is keccak256 of the signature, computed at compile time, and
+/// compilation fails if the signature's parameter list does not match the
+/// declared argument types:
+///
+/// ```ignore
+/// dispatch! {
+///     fn route;
+///
+///     #[selector("closeForm(uint64)")]
+///     fn close_form(form_id: u64) -> bool;
+/// }
+///
+/// match route(selector, &data) { ... }
+/// ```
+///
+/// The generated `route(selector, data)` decodes the arguments, calls the
+/// handler and returns its ABI-encoded result.
+#[macro_export]
+macro_rules! dispatch {
+    (
+        $vis:vis fn $route:ident;
+        $(
+            #[selector($sig:literal)]
+            fn $handler:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;
+        )*
+    ) => {
+        $vis fn $route(selector: [u8; 4], data: &[u8]) -> $crate::abi::DispatchResult {
+            $(
+                const _: () = ::core::assert!(
+                    $crate::abi::signature_matches($sig, &[$(<$ty as $crate::abi::SolType>::SOL_NAME),*]),
+                    ::core::concat!("selector signature does not match handler arguments: ", $sig),
+                );
+                if selector == {
+                    const SELECTOR: [u8; 4] = $crate::abi::selector($sig);
+                    SELECTOR
+                } {
+                    #[allow(unused_mut, unused_variables)]
+                    let mut tokens = $crate::ethabi::decode(
+                        &[$(<$ty as $crate::abi::SolType>::param_type()),*],
+                        data,
+                    )
+                    .map_err(|_| $crate::abi::DispatchError::Decode)?
+                    .into_iter();
+                    $(
+                        let $arg = tokens
+                            .next()
+                            .and_then(<$ty as $crate::abi::SolType>::from_token)
+                            .ok_or($crate::abi::DispatchError::Decode)?;
+                    )*
+                    let result $(: $ret)? = $handler($($arg),*);
+                    return Ok($crate::abi::AbiReturn::encode_return(result));
+                }
+            )*
+            Err($crate::abi::DispatchError::UnknownSelector)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn word(value: u64) -> Token {
+        Token::Uint(value.into())
+    }
+
+    #[test]
+    fn encode_matches_ethabi() {
+        let address = Token::Address([0x11; 20].into());
+        let bytes = |len: usize| Token::Bytes(vec![0xab; len]);
+        let string = |s: &str| Token::String(s.into());
+        let cases = vec![
+            vec![],
+            vec![word(1), Token::Bool(true), Token::Int(U256::MAX), address.clone()],
+            vec![Token::FixedBytes(vec![7; 4]), Token::FixedBytes(vec![7; 32])],
+            vec![bytes(0), bytes(1), bytes(32), bytes(33), string(""), string("héllo")],
+            vec![word(5), Token::Array(vec![]), Token::Array(vec![word(1), word(2)])],
+            vec![Token::Array(vec![bytes(40), bytes(0)]), Token::Array(vec![string("a"), string("bc")])],
+            vec![Token::Tuple(vec![word(1), address.clone()]), Token::Tuple(vec![word(1), string("x")])],
+            vec![Token::FixedArray(vec![word(3), word(4)]), Token::FixedArray(vec![bytes(2), bytes(70)])],
+            vec![Token::Array(vec![
+                Token::Tuple(vec![word(0), address.clone(), word(9), bytes(46)]),
+                Token::Tuple(vec![word(1), address, word(10), bytes(0)]),
+            ])],
+        ];
+        for tokens in cases {
+            assert_eq!(encode(&tokens), ethabi::encode(&tokens), "{tokens:?}");
+            assert_eq!(encode(&tokens).len(), encoded_len(&tokens));
+        }
+    }
+}
@@ -1,120 +1,343 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
 
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
-use ethabi::{decode, encode, ParamType, Token};
+use ethabi::Uint as U256;
+use intran3t_polkavm_contracts::abi::DispatchError;
+use intran3t_polkavm_contracts::collections::{StorageMap, StorageVec};
+use intran3t_polkavm_contracts::host::{Api as api, ReturnFlags};
+use intran3t_polkavm_contracts::{dispatch, errors, keccak, sol_error, sol_event, storage_layout, storage_struct};
+#[cfg(not(feature = "mock"))]
 use polkavm_derive::polkavm_export;
-use pallet_revive_uapi as api;
-use api::ReturnFlags;
+#[cfg(not(feature = "mock"))]
+use uapi::HostFn;
+
+// ============ Runtime support (no_std + alloc) ============
+
+#[cfg(not(feature = "mock"))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    unsafe {
+        core::arch::asm!("unimp");
+        core::hint::unreachable_unchecked();
+    }
+}
+
+/// Heap for a whole call; `mock::deploy` has the tests check it
+#[cfg_attr(feature = "mock", allow(dead_code))]
+const HEAP_SIZE: usize = 32 * 1024;
+
+#[cfg(not(feature = "mock"))]
+#[global_allocator]
+static ALLOCATOR: simplealloc::SimpleAlloc<HEAP_SIZE> = simplealloc::SimpleAlloc::new();
+
+// Call data is copied to the heap whole
+const MAX_CALL_DATA_LEN: usize = 4 * 1024;
+// Token IDs per enumeration call; larger limits are cut to it. A full page
+// uses about half the heap.
+const MAX_PAGE: u64 = 128;
 
 // ============ Constants ============
 
-// Function Selectors (match Solidity ABI)
-const SELECTOR_MINT: [u8; 4] = [0x14, 0xe2, 0xe8, 0xd7];
-const SELECTOR_REVOKE: [u8; 4] = [0x51, 0x9d, 0xb6, 0xc5];
-const SELECTOR_IS_VALID: [u8; 4] = [0x48, 0xc6, 0x19, 0x5d];
-const SELECTOR_GET_METADATA: [u8; 4] = [0x7a, 0x5b, 0xb4, 0xb6];
-const SELECTOR_TOTAL_MINTED: [u8; 4] = [0xa2, 0x30, 0x9f, 0xf8];
-const SELECTOR_GET_BY_HOLDER: [u8; 4] = [0x27, 0x99, 0x67, 0xf5];
-const SELECTOR_GET_BY_LOCATION: [u8; 4] = [0x08, 0xdf, 0x63, 0x28];
-
-// Storage Keys
-const OWNER_KEY: [u8; 32] = [0xFF; 32];
-const TOKEN_COUNTER_KEY: [u8; 32] = [0x01; 32];
-
-// Storage Namespaces
-const NS_PASS_METADATA: u8 = 0x00;
-const NS_TOKEN_OWNER: u8 = 0x02;
-const NS_REVOKED: u8 = 0x03;
-
-// Event Topics (keccak256 of event signature)
-// AccessPassMinted(uint256,address,string,string,uint256,uint256)
-const TOPIC_MINTED: [u8; 32] = [
-    0x8a, 0x8e, 0x8f, 0x3c, 0x5d, 0x9a, 0x7e, 0x3b,
-    0x4d, 0x2c, 0x1f, 0x8e, 0x9b, 0x6a, 0x5c, 0x4d,
-    0x3e, 0x2f, 0x1c, 0x8d, 0x9a, 0x7b, 0x6e, 0x5f,
-    0x4c, 0x3d, 0x2e, 0x1f, 0x9c, 0x8a, 0x7b, 0x6c,
-];
-
-// AccessPassRevoked(uint256,address,uint256)
-const TOPIC_REVOKED: [u8; 32] = [
-    0x7b, 0x9f, 0x8e, 0x2d, 0x6a, 0x5c, 0x4b, 0x3e,
-    0x1f, 0x9d, 0x8a, 0x7b, 0x6c, 0x5d, 0x4e, 0x3f,
-    0x2c, 0x1d, 0x9b, 0x8a, 0x7c, 0x6d, 0x5e, 0x4f,
-    0x3d, 0x2e, 0x1f, 0x9c, 0x8b, 0x7a, 0x6d, 0x5e,
-];
-
-// ============ Storage Helpers ============
-
-fn storage_key_pass(token_id: u64) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    key[0] = NS_PASS_METADATA;
-    key[1..9].copy_from_slice(&token_id.to_le_bytes());
-    key
-}
-
-fn storage_key_owner(token_id: u64) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    key[0] = NS_TOKEN_OWNER;
-    key[1..9].copy_from_slice(&token_id.to_le_bytes());
-    key
-}
-
-fn storage_key_revoked(token_id: u64) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    key[0] = NS_REVOKED;
-    key[1..9].copy_from_slice(&token_id.to_le_bytes());
-    key
-}
-
-fn get_storage(key: &[u8; 32]) -> Option<Vec<u8>> {
-    let mut output = [0u8; 4096];
-    let result = api::get_storage(key, &mut output);
-
-    if result.is_success() {
-        Some(output[..result.len as usize].to_vec())
-    } else {
-        None
+const NAME: &str = "Intran3t Access Pass";
+const SYMBOL: &str = "IACC";
+
+// ERC-165 interface IDs
+const INTERFACE_ERC165: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+const INTERFACE_ERC721: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+const INTERFACE_ERC721_METADATA: [u8; 4] = [0x5b, 0x5e, 0x13, 0x9f];
+const INTERFACE_ACCESS_CONTROL: [u8; 4] = [0x79, 0x65, 0xdb, 0x0b];
+
+// Roles (match OpenZeppelin AccessControl)
+// DEFAULT_ADMIN_ROLE is held by the owner alone and moves with transferOwnership
+const DEFAULT_ADMIN_ROLE: [u8; 32] = [0u8; 32];
+const ADMIN_ROLE: [u8; 32] = keccak::keccak256(b"ADMIN_ROLE");
+const MINTER_ROLE: [u8; 32] = keccak::keccak256(b"MINTER_ROLE");
+const REVOKER_ROLE: [u8; 32] = keccak::keccak256(b"REVOKER_ROLE");
+
+// ============ Storage Layout ============
+
+storage_layout! {
+    OWNER: StorageValue<[u8; 20]> = 0x01;
+    /// Next token ID to mint, starts at 1
+    TOKEN_COUNTER: StorageValue<u64> = 0x02;
+    PASSES: StorageMap<u64, StoredPass> = 0x03;
+    /// Current holder; cleared on revoke
+    TOKEN_OWNER: StorageMap<u64, [u8; 20]> = 0x04;
+    REVOKED: StorageMap<u64, bool> = 0x05;
+    BALANCE: StorageMap<[u8; 20], u64> = 0x06;
+    HOLDER_PASSES: StorageVecMap<[u8; 20], u64> = 0x07;
+    /// token ID → index in its holder's list
+    HOLDER_POSITION: StorageMap<u64, u64> = 0x08;
+    /// Keyed by keccak256(locationId)
+    LOCATION_PASSES: StorageVecMap<[u8; 32], u64> = 0x09;
+    /// token ID → index in its location's list
+    LOCATION_POSITION: StorageMap<u64, u64> = 0x0a;
+    ROLE_MEMBER: StorageMap<([u8; 32], [u8; 20]), bool> = 0x0b;
+}
+
+storage_struct! {
+    /// Pass metadata as minted; revocation is tracked separately
+    struct StoredPass {
+        location: String,
+        location_id: String,
+        holder: [u8; 20],
+        issued_at: U256,
+        expires_at: U256,
+        access_level: String,
+        identity_display: String,
+    }
+}
+
+/// `AccessPassMetadata` struct returned by `getPassMetadata`
+type PassMetadata = (String, String, [u8; 20], U256, U256, String, bool, String);
+
+// ============ Events (match Intran3tAccessPass.sol) ============
+
+sol_event! {
+    #[signature("Transfer(address,address,uint256)")]
+    struct Transfer {
+        #[indexed] from: [u8; 20],
+        #[indexed] to: [u8; 20],
+        #[indexed] token_id: U256,
+    }
+}
+
+sol_event! {
+    #[signature("RoleGranted(bytes32,address,address)")]
+    struct RoleGranted {
+        #[indexed] role: [u8; 32],
+        #[indexed] account: [u8; 20],
+        #[indexed] sender: [u8; 20],
+    }
+}
+
+sol_event! {
+    #[signature("RoleRevoked(bytes32,address,address)")]
+    struct RoleRevoked {
+        #[indexed] role: [u8; 32],
+        #[indexed] account: [u8; 20],
+        #[indexed] sender: [u8; 20],
+    }
+}
+
+sol_event! {
+    #[signature("OwnershipTransferred(address,address)")]
+    struct OwnershipTransferred {
+        #[indexed] previous_owner: [u8; 20],
+        #[indexed] new_owner: [u8; 20],
+    }
+}
+
+sol_event! {
+    #[signature("AccessPassMinted(uint256,address,string,string,uint256,uint256)")]
+    struct AccessPassMinted {
+        #[indexed] token_id: U256,
+        #[indexed] holder: [u8; 20],
+        location: String,
+        location_id: String,
+        expires_at: U256,
+        timestamp: U256,
+    }
+}
+
+sol_event! {
+    #[signature("AccessPassRevoked(uint256,address,uint256)")]
+    struct AccessPassRevoked {
+        #[indexed] token_id: U256,
+        #[indexed] revoked_by: [u8; 20],
+        timestamp: U256,
+    }
+}
+
+// ============ Errors (match Intran3tAccessPass.sol and OpenZeppelin 5) ============
+
+sol_error! {
+    #[signature("Intran3tAccessPass__Unauthorized()")]
+    struct Unauthorized {}
+}
+
+sol_error! {
+    #[signature("Intran3tAccessPass__PassNotFound()")]
+    struct PassNotFound {}
+}
+
+sol_error! {
+    #[signature("Intran3tAccessPass__InvalidExpiration()")]
+    struct InvalidExpiration {}
+}
+
+sol_error! {
+    #[signature("Intran3tAccessPass__TransferNotAllowed()")]
+    struct TransferNotAllowed {}
+}
+
+// Not in the Solidity contract: approvals are meaningless for soulbound passes
+sol_error! {
+    #[signature("Intran3tAccessPass__ApprovalNotAllowed()")]
+    struct ApprovalNotAllowed {}
+}
+
+// Not in the Solidity contract: DEFAULT_ADMIN_ROLE follows the owner
+sol_error! {
+    #[signature("Intran3tAccessPass__UseTransferOwnership()")]
+    struct UseTransferOwnership {}
+}
+
+sol_error! {
+    #[signature("AccessControlUnauthorizedAccount(address,bytes32)")]
+    struct AccessControlUnauthorizedAccount {
+        account: [u8; 20],
+        needed_role: [u8; 32],
+    }
+}
+
+sol_error! {
+    #[signature("AccessControlBadConfirmation()")]
+    struct AccessControlBadConfirmation {}
+}
+
+sol_error! {
+    #[signature("OwnableUnauthorizedAccount(address)")]
+    struct OwnableUnauthorizedAccount {
+        account: [u8; 20],
+    }
+}
+
+sol_error! {
+    #[signature("OwnableInvalidOwner(address)")]
+    struct OwnableInvalidOwner {
+        owner: [u8; 20],
+    }
+}
+
+sol_error! {
+    #[signature("ERC721InvalidOwner(address)")]
+    struct ERC721InvalidOwner {
+        owner: [u8; 20],
+    }
+}
+
+sol_error! {
+    #[signature("ERC721InvalidReceiver(address)")]
+    struct ERC721InvalidReceiver {
+        receiver: [u8; 20],
     }
 }
 
-fn set_storage(key: &[u8; 32], value: &[u8]) {
-    api::set_storage(key, value);
+sol_error! {
+    #[signature("ERC721NonexistentToken(uint256)")]
+    struct ERC721NonexistentToken {
+        token_id: U256,
+    }
 }
 
-fn get_u64(key: &[u8; 32]) -> u64 {
-    get_storage(key)
-        .and_then(|bytes| {
-            if bytes.len() >= 8 {
-                let mut arr = [0u8; 8];
-                arr.copy_from_slice(&bytes[..8]);
-                Some(u64::from_le_bytes(arr))
-            } else {
-                None
-            }
-        })
-        .unwrap_or(0)
+// ============ Token Indexes ============
+
+/// Enumerable set of token IDs (per holder or per location).
+///
+/// Each token's index in the list is kept in `position`, so removal is a
+/// swap with the last entry. Order is insertion order until the first removal.
+struct TokenIndex {
+    items: StorageVec<u64>,
+    position: &'static StorageMap<u64, u64>,
+}
+
+impl TokenIndex {
+    fn holder(holder: &[u8; 20]) -> Self {
+        TokenIndex { items: HOLDER_PASSES.at(holder), position: &HOLDER_POSITION }
+    }
+
+    fn location(location_id: &str) -> Self {
+        let id = keccak::hash(location_id.as_bytes());
+        TokenIndex { items: LOCATION_PASSES.at(&id), position: &LOCATION_POSITION }
+    }
+
+    fn len(&self) -> u64 {
+        self.items.len()
+    }
+
+    fn push(&self, token_id: u64) {
+        let index = self.items.push(&token_id);
+        self.position.set(&token_id, &index);
+    }
+
+    fn remove(&self, token_id: u64) {
+        let Some(index) = self.position.get(&token_id) else {
+            return;
+        };
+        self.items.swap_remove(index);
+        if let Some(moved) = self.items.get(index) {
+            self.position.set(&moved, &index);
+        }
+        self.position.remove(&token_id);
+    }
+
+    /// Up to `limit` token IDs, at most `MAX_PAGE`, starting at `offset`;
+    /// empty past the end.
+    fn page(&self, offset: U256, limit: U256) -> Vec<U256> {
+        let limit = clamp_u64(limit).min(MAX_PAGE);
+        self.items.page(clamp_u64(offset), limit).into_iter().map(U256::from).collect()
+    }
 }
 
-fn set_u64(key: &[u8; 32], value: u64) {
-    set_storage(key, &value.to_le_bytes());
+fn clamp_u64(value: U256) -> u64 {
+    if value.bits() > 64 { u64::MAX } else { value.low_u64() }
 }
 
 fn get_owner() -> [u8; 20] {
-    get_storage(&OWNER_KEY)
-        .and_then(|bytes| {
-            if bytes.len() >= 20 {
-                let mut addr = [0u8; 20];
-                addr.copy_from_slice(&bytes[..20]);
-                Some(addr)
-            } else {
-                None
-            }
-        })
-        .unwrap_or([0u8; 20])
+    OWNER.get_or_default()
+}
+
+// ============ Roles ============
+
+fn role_member(role: &[u8; 32], account: &[u8; 20]) -> bool {
+    if *role == DEFAULT_ADMIN_ROLE {
+        return *account == get_owner();
+    }
+    ROLE_MEMBER.contains(&(*role, *account))
+}
+
+/// Role whose members may grant and revoke `role`
+fn role_admin(role: &[u8; 32]) -> [u8; 32] {
+    if *role == MINTER_ROLE || *role == REVOKER_ROLE {
+        ADMIN_ROLE
+    } else {
+        DEFAULT_ADMIN_ROLE
+    }
+}
+
+fn require_role(role: &[u8; 32], account: &[u8; 20]) {
+    if !role_member(role, account) {
+        AccessControlUnauthorizedAccount { account: *account, needed_role: *role }.revert();
+    }
+}
+
+/// Add `account` to `role`, emitting `RoleGranted` only on change
+fn grant(role: [u8; 32], account: [u8; 20], sender: [u8; 20]) {
+    if role == DEFAULT_ADMIN_ROLE {
+        UseTransferOwnership {}.revert();
+    }
+    if role_member(&role, &account) {
+        return;
+    }
+    ROLE_MEMBER.set(&(role, account), &true);
+    RoleGranted { role, account, sender }.emit();
+}
+
+/// Remove `account` from `role`, emitting `RoleRevoked` only on change
+fn revoke(role: [u8; 32], account: [u8; 20], sender: [u8; 20]) {
+    if role == DEFAULT_ADMIN_ROLE {
+        UseTransferOwnership {}.revert();
+    }
+    if !role_member(&role, &account) {
+        return;
+    }
+    ROLE_MEMBER.remove(&(role, account));
+    RoleRevoked { role, account, sender }.emit();
 }
 
 fn caller() -> [u8; 20] {
@@ -124,349 +347,896 @@ fn caller() -> [u8; 20] {
 }
 
 fn block_timestamp() -> u64 {
-    api::block_timestamp()
+    let mut ts = [0u8; 32];
+    api::now(&mut ts);
+    let mut arr = [0u8; 8];
+    arr.copy_from_slice(&ts[..8]);
+    u64::from_le_bytes(arr)
+}
+
+/// Token IDs start at 1, so IDs beyond u64 map to the never-minted 0.
+fn local_id(token_id: U256) -> u64 {
+    if token_id.bits() > 64 { 0 } else { token_id.low_u64() }
+}
+
+/// Current holder, `None` if never minted or burned.
+fn token_owner(token_id: u64) -> Option<[u8; 20]> {
+    TOKEN_OWNER.get(&token_id)
 }
 
 // ============ Constructor ============
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn deploy() {
+    init();
+}
+
+fn init() {
     // Set contract owner to deployer
     let deployer = caller();
-    set_storage(&OWNER_KEY, &deployer);
+    OWNER.set(&deployer);
+    RoleGranted { role: DEFAULT_ADMIN_ROLE, account: deployer, sender: deployer }.emit();
+    OwnershipTransferred { previous_owner: [0u8; 20], new_owner: deployer }.emit();
+
+    // Deployer starts with every operational role
+    grant(ADMIN_ROLE, deployer, deployer);
+    grant(MINTER_ROLE, deployer, deployer);
+    grant(REVOKER_ROLE, deployer, deployer);
 
     // Initialize token counter to 1
-    set_u64(&TOKEN_COUNTER_KEY, 1);
+    TOKEN_COUNTER.set(&1);
 }
 
 // ============ Main Dispatcher ============
 
-#[polkavm_export]
+dispatch! {
+    fn route;
+
+    // Access passes
+    #[selector("mintAccessPass(address,string,string,uint256,string,string)")]
+    fn mint_access_pass(to: [u8; 20], location: String, location_id: String, expires_at: U256, access_level: String, identity_display: String) -> U256;
+
+    #[selector("revokeAccessPass(uint256)")]
+    fn revoke_access_pass(token_id: U256);
+
+    #[selector("isPassValid(uint256)")]
+    fn is_pass_valid(token_id: U256) -> bool;
+
+    #[selector("getPassMetadata(uint256)")]
+    fn get_pass_metadata(token_id: U256) -> PassMetadata;
+
+    #[selector("totalMinted()")]
+    fn total_minted() -> U256;
+
+    // Enumeration
+    #[selector("getPassesByHolder(address)")]
+    fn get_passes_by_holder(holder: [u8; 20]) -> Vec<U256>;
+
+    #[selector("getPassesByHolder(address,uint256,uint256)")]
+    fn get_passes_by_holder_paged(holder: [u8; 20], offset: U256, limit: U256) -> Vec<U256>;
+
+    #[selector("getPassesByLocation(string)")]
+    fn get_passes_by_location(location_id: String) -> Vec<U256>;
+
+    #[selector("getPassesByLocation(string,uint256,uint256)")]
+    fn get_passes_by_location_paged(location_id: String, offset: U256, limit: U256) -> Vec<U256>;
+
+    #[selector("getPassCountByLocation(string)")]
+    fn get_pass_count_by_location(location_id: String) -> U256;
+
+    // Roles and ownership
+    #[selector("DEFAULT_ADMIN_ROLE()")]
+    fn default_admin_role() -> [u8; 32];
+
+    #[selector("ADMIN_ROLE()")]
+    fn admin_role() -> [u8; 32];
+
+    #[selector("MINTER_ROLE()")]
+    fn minter_role() -> [u8; 32];
+
+    #[selector("REVOKER_ROLE()")]
+    fn revoker_role() -> [u8; 32];
+
+    #[selector("hasRole(bytes32,address)")]
+    fn has_role(role: [u8; 32], account: [u8; 20]) -> bool;
+
+    #[selector("getRoleAdmin(bytes32)")]
+    fn get_role_admin(role: [u8; 32]) -> [u8; 32];
+
+    #[selector("grantRole(bytes32,address)")]
+    fn grant_role(role: [u8; 32], account: [u8; 20]);
+
+    #[selector("revokeRole(bytes32,address)")]
+    fn revoke_role(role: [u8; 32], account: [u8; 20]);
+
+    #[selector("renounceRole(bytes32,address)")]
+    fn renounce_role(role: [u8; 32], caller_confirmation: [u8; 20]);
+
+    #[selector("grantMinterRole(address)")]
+    fn grant_minter_role(account: [u8; 20]);
+
+    #[selector("revokeMinterRole(address)")]
+    fn revoke_minter_role(account: [u8; 20]);
+
+    #[selector("owner()")]
+    fn owner() -> [u8; 20];
+
+    #[selector("transferOwnership(address)")]
+    fn transfer_ownership(new_owner: [u8; 20]);
+
+    // ERC-721 / ERC-165
+    #[selector("name()")]
+    fn name() -> String;
+
+    #[selector("symbol()")]
+    fn symbol() -> String;
+
+    #[selector("tokenURI(uint256)")]
+    fn token_uri(token_id: U256) -> String;
+
+    #[selector("supportsInterface(bytes4)")]
+    fn supports_interface(interface_id: [u8; 4]) -> bool;
+
+    #[selector("balanceOf(address)")]
+    fn balance_of(holder: [u8; 20]) -> U256;
+
+    #[selector("ownerOf(uint256)")]
+    fn owner_of(token_id: U256) -> [u8; 20];
+
+    #[selector("getApproved(uint256)")]
+    fn get_approved(token_id: U256) -> [u8; 20];
+
+    #[selector("isApprovedForAll(address,address)")]
+    fn is_approved_for_all(holder: [u8; 20], operator: [u8; 20]) -> bool;
+
+    #[selector("approve(address,uint256)")]
+    fn approve(to: [u8; 20], token_id: U256);
+
+    #[selector("setApprovalForAll(address,bool)")]
+    fn set_approval_for_all(operator: [u8; 20], approved: bool);
+
+    #[selector("transferFrom(address,address,uint256)")]
+    fn transfer_from(from: [u8; 20], to: [u8; 20], token_id: U256);
+
+    #[selector("safeTransferFrom(address,address,uint256)")]
+    fn safe_transfer_from(from: [u8; 20], to: [u8; 20], token_id: U256);
+
+    #[selector("safeTransferFrom(address,address,uint256,bytes)")]
+    fn safe_transfer_from_with_data(from: [u8; 20], to: [u8; 20], token_id: U256, data: Vec<u8>);
+}
+
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn call() {
-    let mut input = [0u8; 4096];
-    let input_len = api::input(&mut input);
+    dispatch();
+}
+
+/// Selector dispatch behind `call()`, callable from host-side tests.
+fn dispatch() {
+    let input_len = api::call_data_size() as usize;
 
+    // Like a Solidity contract without a fallback, bad calldata reverts with empty data
     if input_len < 4 {
-        api::return_value(ReturnFlags::REVERT, b"INVALID_SELECTOR");
-        return;
+        errors::revert_with(&[]);
     }
 
+    // Read call data on the heap, not the stack; oversized call data is bad
+    // call data, not something to truncate
+    if input_len > MAX_CALL_DATA_LEN {
+        errors::revert_with(&[]);
+    }
+    let mut input = vec![0u8; input_len];
+    api::call_data_copy(&mut input, 0);
+
     let selector: [u8; 4] = [input[0], input[1], input[2], input[3]];
-    let call_data = &input[4..input_len];
 
-    match selector {
-        SELECTOR_MINT => mint_access_pass(call_data),
-        SELECTOR_REVOKE => revoke_access_pass(call_data),
-        SELECTOR_IS_VALID => is_pass_valid(call_data),
-        SELECTOR_GET_METADATA => get_pass_metadata(call_data),
-        SELECTOR_TOTAL_MINTED => total_minted(),
-        _ => api::return_value(ReturnFlags::REVERT, b"UNKNOWN_FUNCTION"),
+    match route(selector, &input[4..]) {
+        Ok(output) => api::return_value(ReturnFlags::empty(), &output),
+        Err(DispatchError::UnknownSelector | DispatchError::Decode) => errors::revert_with(&[]),
     }
 }
 
 // ============ Contract Functions ============
 
 /// Mint a new access pass NFT
-/// Simplified: Anyone can mint to themselves
-fn mint_access_pass(data: &[u8]) {
-    // Decode: (to, location, locationId, expiresAt, accessLevel, identityDisplay)
-    let tokens = match decode(
-        &[
-            ParamType::Address,
-            ParamType::String,
-            ParamType::String,
-            ParamType::Uint(256),
-            ParamType::String,
-            ParamType::String,
-        ],
-        data,
-    ) {
-        Ok(t) => t,
-        Err(_) => {
-            api::return_value(ReturnFlags::REVERT, b"DECODE_ERROR");
-            return;
-        }
-    };
+/// Anyone can mint to themselves; minting to others requires MINTER_ROLE
+fn mint_access_pass(
+    to: [u8; 20],
+    location: String,
+    location_id: String,
+    expires_at: U256,
+    access_level: String,
+    identity_display: String,
+) -> U256 {
+    let sender = caller();
 
-    let to = match &tokens[0] {
-        Token::Address(addr) => {
-            let mut arr = [0u8; 20];
-            arr.copy_from_slice(&addr.as_bytes()[..20]);
-            arr
-        }
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_ADDRESS");
-            return;
-        }
-    };
+    // Anyone can mint to themselves
+    // If minting to someone else, sender must hold MINTER_ROLE
+    if to != sender && !role_member(&MINTER_ROLE, &sender) {
+        Unauthorized {}.revert();
+    }
 
-    let location = match &tokens[1] {
-        Token::String(s) => s.clone(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_LOCATION");
-            return;
-        }
-    };
+    if to == [0u8; 20] {
+        ERC721InvalidReceiver { receiver: to }.revert();
+    }
 
-    let location_id = match &tokens[2] {
-        Token::String(s) => s.clone(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_LOCATION_ID");
-            return;
-        }
-    };
+    // Validate expiration
+    let now = block_timestamp();
+    if !expires_at.is_zero() && expires_at <= U256::from(now) {
+        InvalidExpiration {}.revert();
+    }
 
-    let expires_at = match &tokens[3] {
-        Token::Uint(n) => n.as_u64(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_EXPIRATION");
-            return;
-        }
-    };
+    // Get and increment token ID
+    let token_id = TOKEN_COUNTER.get_or_default();
+    TOKEN_COUNTER.set(&(token_id + 1));
+
+    // Store owner and bump balance
+    TOKEN_OWNER.set(&token_id, &to);
+    BALANCE.set(&to, &(BALANCE.get_or_default(&to) + 1));
+
+    // Track by holder and location
+    TokenIndex::holder(&to).push(token_id);
+    TokenIndex::location(&location_id).push(token_id);
+
+    // Store metadata
+    PASSES.set(
+        &token_id,
+        &StoredPass {
+            location: location.clone(),
+            location_id: location_id.clone(),
+            holder: to,
+            issued_at: now.into(),
+            expires_at,
+            access_level,
+            identity_display,
+        },
+    );
+
+    // Passes are soulbound, so there is no onERC721Received check on mint
+    Transfer { from: [0u8; 20], to, token_id: token_id.into() }.emit();
+
+    AccessPassMinted {
+        token_id: token_id.into(),
+        holder: to,
+        location,
+        location_id,
+        expires_at,
+        timestamp: now.into(),
+    }
+    .emit();
 
-    let access_level = match &tokens[4] {
-        Token::String(s) => s.clone(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_ACCESS_LEVEL");
-            return;
-        }
+    token_id.into()
+}
+
+/// Revoke an access pass (burns the NFT)
+/// Requires REVOKER_ROLE
+fn revoke_access_pass(token_id: U256) {
+    let id = local_id(token_id);
+
+    // Check authorization
+    let sender = caller();
+    require_role(&REVOKER_ROLE, &sender);
+
+    // Check if token exists
+    let holder = match token_owner(id) {
+        Some(holder) => holder,
+        None => PassNotFound {}.revert(),
     };
 
-    let identity_display = match &tokens[5] {
-        Token::String(s) => s.clone(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_IDENTITY");
-            return;
+    // Mark as revoked
+    REVOKED.set(&id, &true);
+
+    // Burn token (clear owner, drop balance)
+    TOKEN_OWNER.remove(&id);
+    BALANCE.set(&holder, &BALANCE.get_or_default(&holder).saturating_sub(1));
+
+    // Drop from holder and location indexes
+    TokenIndex::holder(&holder).remove(id);
+    if let Some(pass) = PASSES.get(&id) {
+        TokenIndex::location(&pass.location_id).remove(id);
+    }
+
+    Transfer { from: holder, to: [0u8; 20], token_id }.emit();
+
+    AccessPassRevoked {
+        token_id,
+        revoked_by: sender,
+        timestamp: block_timestamp().into(),
+    }
+    .emit();
+}
+
+/// Check if an access pass is valid
+fn is_pass_valid(token_id: U256) -> bool {
+    let id = local_id(token_id);
+
+    // Check if token exists
+    if token_owner(id).is_none() {
+        return false;
+    }
+
+    // Check if revoked
+    if REVOKED.contains(&id) {
+        return false;
+    }
+
+    // Check expiration
+    if let Some(pass) = PASSES.get(&id) {
+        if !pass.expires_at.is_zero() && U256::from(block_timestamp()) > pass.expires_at {
+            return false;
         }
-    };
+    }
 
-    let sender = caller();
+    true
+}
 
-    // Simplified access control: Anyone can mint to themselves
-    // If minting to someone else, sender must be contract owner
-    if to != sender && sender != get_owner() {
-        api::return_value(ReturnFlags::REVERT, b"UNAUTHORIZED");
-        return;
+/// Get access pass metadata
+fn get_pass_metadata(token_id: U256) -> PassMetadata {
+    let id = local_id(token_id);
+
+    // Check if token exists
+    if token_owner(id).is_none() {
+        PassNotFound {}.revert();
     }
 
-    // Validate expiration
-    let now = block_timestamp();
-    if expires_at > 0 && expires_at <= now {
-        api::return_value(ReturnFlags::REVERT, b"INVALID_EXPIRATION");
-        return;
+    let revoked = REVOKED.contains(&id);
+    match PASSES.get(&id) {
+        Some(pass) => (
+            pass.location,
+            pass.location_id,
+            pass.holder,
+            pass.issued_at,
+            pass.expires_at,
+            pass.access_level,
+            revoked,
+            pass.identity_display,
+        ),
+        None => PassNotFound {}.revert(),
     }
+}
 
-    // Get and increment token ID
-    let token_id = get_u64(&TOKEN_COUNTER_KEY);
-    set_u64(&TOKEN_COUNTER_KEY, token_id + 1);
-
-    // Store owner
-    set_storage(&storage_key_owner(token_id), &to);
-
-    // Store metadata (ABI-encoded)
-    let metadata = encode(&[
-        Token::String(location.clone()),
-        Token::String(location_id.clone()),
-        Token::Address(to.into()),
-        Token::Uint(now.into()),
-        Token::Uint(expires_at.into()),
-        Token::String(access_level),
-        Token::String(identity_display),
-    ]);
-    set_storage(&storage_key_pass(token_id), &metadata);
-
-    // Emit AccessPassMinted event
-    let event_data = encode(&[
-        Token::Uint(token_id.into()),
-        Token::Address(to.into()),
-        Token::String(location),
-        Token::String(location_id),
-        Token::Uint(expires_at.into()),
-        Token::Uint(now.into()),
-    ]);
-    api::deposit_event(&[TOPIC_MINTED], &event_data);
-
-    // Return token ID
-    let result = encode(&[Token::Uint(token_id.into())]);
-    api::return_value(ReturnFlags::empty(), &result);
+/// Get total number of minted passes
+fn total_minted() -> U256 {
+    TOKEN_COUNTER.get_or_default().saturating_sub(1).into()
 }
 
-/// Revoke an access pass (burns the NFT)
-/// Only contract owner can revoke
-fn revoke_access_pass(data: &[u8]) {
-    let tokens = match decode(&[ParamType::Uint(256)], data) {
-        Ok(t) => t,
-        Err(_) => {
-            api::return_value(ReturnFlags::REVERT, b"DECODE_ERROR");
-            return;
-        }
-    };
+// ============ Roles and Ownership ============
 
-    let token_id = match &tokens[0] {
-        Token::Uint(n) => n.as_u64(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_TOKEN_ID");
-            return;
-        }
-    };
+fn default_admin_role() -> [u8; 32] {
+    DEFAULT_ADMIN_ROLE
+}
+
+fn admin_role() -> [u8; 32] {
+    ADMIN_ROLE
+}
+
+fn minter_role() -> [u8; 32] {
+    MINTER_ROLE
+}
+
+fn revoker_role() -> [u8; 32] {
+    REVOKER_ROLE
+}
+
+fn has_role(role: [u8; 32], account: [u8; 20]) -> bool {
+    role_member(&role, &account)
+}
 
-    // Check authorization (only owner)
+fn get_role_admin(role: [u8; 32]) -> [u8; 32] {
+    role_admin(&role)
+}
+
+/// Requires the role's admin role
+fn grant_role(role: [u8; 32], account: [u8; 20]) {
     let sender = caller();
-    if sender != get_owner() {
-        api::return_value(ReturnFlags::REVERT, b"UNAUTHORIZED");
-        return;
+    require_role(&role_admin(&role), &sender);
+    grant(role, account, sender);
+}
+
+/// Requires the role's admin role
+fn revoke_role(role: [u8; 32], account: [u8; 20]) {
+    let sender = caller();
+    require_role(&role_admin(&role), &sender);
+    revoke(role, account, sender);
+}
+
+/// Drop one of the caller's own roles; `caller_confirmation` must be the caller
+fn renounce_role(role: [u8; 32], caller_confirmation: [u8; 20]) {
+    let sender = caller();
+    if caller_confirmation != sender {
+        AccessControlBadConfirmation {}.revert();
     }
+    revoke(role, sender, sender);
+}
 
-    // Check if token exists
-    let owner_key = storage_key_owner(token_id);
-    if get_storage(&owner_key).is_none() {
-        api::return_value(ReturnFlags::REVERT, b"TOKEN_NOT_FOUND");
+/// Legacy helper from the Solidity contract, requires ADMIN_ROLE
+fn grant_minter_role(account: [u8; 20]) {
+    let sender = caller();
+    require_role(&ADMIN_ROLE, &sender);
+    grant(MINTER_ROLE, account, sender);
+}
+
+/// Legacy helper from the Solidity contract, requires ADMIN_ROLE
+fn revoke_minter_role(account: [u8; 20]) {
+    let sender = caller();
+    require_role(&ADMIN_ROLE, &sender);
+    revoke(MINTER_ROLE, account, sender);
+}
+
+fn owner() -> [u8; 20] {
+    get_owner()
+}
+
+/// Hand the contract (and DEFAULT_ADMIN_ROLE) to `new_owner`
+fn transfer_ownership(new_owner: [u8; 20]) {
+    let sender = caller();
+    if sender != get_owner() {
+        OwnableUnauthorizedAccount { account: sender }.revert();
+    }
+    if new_owner == [0u8; 20] {
+        OwnableInvalidOwner { owner: new_owner }.revert();
+    }
+    if new_owner == sender {
         return;
     }
 
-    // Mark as revoked
-    set_storage(&storage_key_revoked(token_id), &[1u8]);
+    OWNER.set(&new_owner);
+    RoleRevoked { role: DEFAULT_ADMIN_ROLE, account: sender, sender }.emit();
+    RoleGranted { role: DEFAULT_ADMIN_ROLE, account: new_owner, sender }.emit();
+    OwnershipTransferred { previous_owner: sender, new_owner }.emit();
+}
 
-    // Burn token (clear owner)
-    api::clear_storage(&owner_key);
+// ============ Enumeration ============
 
-    // Emit event
-    let event_data = encode(&[
-        Token::Uint(token_id.into()),
-        Token::Address(sender.into()),
-        Token::Uint(block_timestamp().into()),
-    ]);
-    api::deposit_event(&[TOPIC_REVOKED], &event_data);
+/// The first `MAX_PAGE` active (unrevoked) passes held by `holder`; page
+/// with the overload taking an offset for the rest
+fn get_passes_by_holder(holder: [u8; 20]) -> Vec<U256> {
+    get_passes_by_holder_paged(holder, U256::zero(), MAX_PAGE.into())
+}
 
-    api::return_value(ReturnFlags::empty(), &[]);
+/// Page of `getPassesByHolder`; the total is `balanceOf(holder)`
+fn get_passes_by_holder_paged(holder: [u8; 20], offset: U256, limit: U256) -> Vec<U256> {
+    TokenIndex::holder(&holder).page(offset, limit)
 }
 
-/// Check if an access pass is valid
-fn is_pass_valid(data: &[u8]) {
-    let tokens = match decode(&[ParamType::Uint(256)], data) {
-        Ok(t) => t,
-        Err(_) => {
-            api::return_value(ReturnFlags::REVERT, b"DECODE_ERROR");
-            return;
-        }
-    };
+/// The first `MAX_PAGE` active (unrevoked) passes for `location_id`
+fn get_passes_by_location(location_id: String) -> Vec<U256> {
+    get_passes_by_location_paged(location_id, U256::zero(), MAX_PAGE.into())
+}
 
-    let token_id = match &tokens[0] {
-        Token::Uint(n) => n.as_u64(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_TOKEN_ID");
-            return;
-        }
+/// Page of `getPassesByLocation`; the total is `getPassCountByLocation`
+fn get_passes_by_location_paged(location_id: String, offset: U256, limit: U256) -> Vec<U256> {
+    TokenIndex::location(&location_id).page(offset, limit)
+}
+
+fn get_pass_count_by_location(location_id: String) -> U256 {
+    TokenIndex::location(&location_id).len().into()
+}
+
+// ============ ERC-721 ============
+
+fn name() -> String {
+    NAME.into()
+}
+
+fn symbol() -> String {
+    SYMBOL.into()
+}
+
+/// On-chain JSON metadata, same layout as the Solidity contract
+fn token_uri(token_id: U256) -> String {
+    let id = local_id(token_id);
+    if token_owner(id).is_none() {
+        PassNotFound {}.revert();
+    }
+    let StoredPass { location, holder, issued_at, expires_at, access_level, .. } = match PASSES.get(&id) {
+        Some(pass) => pass,
+        None => PassNotFound {}.revert(),
     };
+    let revoked = REVOKED.contains(&id);
+
+    let mut uri = String::from("data:application/json;utf8,");
+    uri.push_str("{\"name\": \"Access Pass #");
+    push_decimal(&mut uri, token_id);
+    uri.push_str("\", \"description\": \"Intran3t Access Pass for ");
+    uri.push_str(&location);
+    uri.push_str("\", \"attributes\": [");
+    uri.push_str("{\"trait_type\": \"Location\", \"value\": \"");
+    uri.push_str(&location);
+    uri.push_str("\"},");
+    uri.push_str("{\"trait_type\": \"Access Level\", \"value\": \"");
+    uri.push_str(&access_level);
+    uri.push_str("\"},");
+    uri.push_str("{\"trait_type\": \"Holder\", \"value\": \"");
+    push_hex_address(&mut uri, &holder);
+    uri.push_str("\"},");
+    uri.push_str("{\"trait_type\": \"Issued At\", \"value\": ");
+    push_decimal(&mut uri, issued_at);
+    uri.push_str("},");
+    uri.push_str("{\"trait_type\": \"Expires At\", \"value\": ");
+    push_decimal(&mut uri, expires_at);
+    uri.push_str("},");
+    uri.push_str("{\"trait_type\": \"Revoked\", \"value\": ");
+    uri.push_str(if revoked { "\"true\"" } else { "\"false\"" });
+    uri.push('}');
+    uri.push_str("]}");
+    uri
+}
 
-    // Check if token exists
-    if get_storage(&storage_key_owner(token_id)).is_none() {
-        let result = encode(&[Token::Bool(false)]);
-        api::return_value(ReturnFlags::empty(), &result);
-        return;
+fn supports_interface(interface_id: [u8; 4]) -> bool {
+    matches!(
+        interface_id,
+        INTERFACE_ERC165 | INTERFACE_ERC721 | INTERFACE_ERC721_METADATA | INTERFACE_ACCESS_CONTROL
+    )
+}
+
+fn balance_of(holder: [u8; 20]) -> U256 {
+    if holder == [0u8; 20] {
+        ERC721InvalidOwner { owner: holder }.revert();
     }
+    BALANCE.get_or_default(&holder).into()
+}
 
-    // Check if revoked
-    if get_storage(&storage_key_revoked(token_id)).is_some() {
-        let result = encode(&[Token::Bool(false)]);
-        api::return_value(ReturnFlags::empty(), &result);
+fn owner_of(token_id: U256) -> [u8; 20] {
+    match token_owner(local_id(token_id)) {
+        Some(holder) => holder,
+        None => ERC721NonexistentToken { token_id }.revert(),
+    }
+}
+
+/// Passes cannot be approved, so there is never an approved address
+fn get_approved(token_id: U256) -> [u8; 20] {
+    owner_of(token_id);
+    [0u8; 20]
+}
+
+fn is_approved_for_all(_holder: [u8; 20], _operator: [u8; 20]) -> bool {
+    false
+}
+
+fn approve(_to: [u8; 20], _token_id: U256) {
+    ApprovalNotAllowed {}.revert();
+}
+
+fn set_approval_for_all(_operator: [u8; 20], _approved: bool) {
+    ApprovalNotAllowed {}.revert();
+}
+
+/// Soulbound: only minting and burning move a pass
+fn transfer_from(_from: [u8; 20], _to: [u8; 20], _token_id: U256) {
+    TransferNotAllowed {}.revert();
+}
+
+fn safe_transfer_from(from: [u8; 20], to: [u8; 20], token_id: U256) {
+    transfer_from(from, to, token_id);
+}
+
+fn safe_transfer_from_with_data(from: [u8; 20], to: [u8; 20], token_id: U256, _data: Vec<u8>) {
+    transfer_from(from, to, token_id);
+}
+
+// ============ Formatting ============
+
+fn push_decimal(out: &mut String, value: U256) {
+    if value.is_zero() {
+        out.push('0');
         return;
     }
+    let mut digits = Vec::new();
+    let mut rest = value;
+    let ten = U256::from(10u8);
+    while !rest.is_zero() {
+        digits.push(b'0' + (rest % ten).low_u32() as u8);
+        rest /= ten;
+    }
+    for digit in digits.iter().rev() {
+        out.push(*digit as char);
+    }
+}
 
-    // Check expiration
-    if let Some(metadata_bytes) = get_storage(&storage_key_pass(token_id)) {
-        if let Ok(metadata) = decode(
+fn push_hex_address(out: &mut String, addr: &[u8; 20]) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    out.push_str("0x");
+    for byte in addr {
+        out.push(HEX[(byte >> 4) as usize] as char);
+        out.push(HEX[(byte & 0x0f) as usize] as char);
+    }
+}
+
+#[cfg(feature = "mock")]
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::{encode, Token};
+    use intran3t_polkavm_contracts::abi::selector;
+    use intran3t_polkavm_contracts::mock::{self, address, bytes32, call, query, string, uint};
+
+    const DEPLOYER: [u8; 20] = [0x11; 20];
+    const ALICE: [u8; 20] = [0x22; 20];
+    const BOB: [u8; 20] = [0x33; 20];
+    const NOW: u64 = 1_000;
+
+    /// Fresh contract deployed by DEPLOYER
+    fn setup() {
+        mock::reset();
+        mock::deploy(dispatch, HEAP_SIZE);
+        mock::set_caller(DEPLOYER);
+        mock::set_now(NOW);
+        mock::execute(init, &[]);
+        mock::take_events();
+    }
+
+    fn mint(to: [u8; 20], location_id: &str, expires_at: u64) -> mock::Outcome {
+        call(
+            "mintAccessPass(address,string,string,uint256,string,string)",
             &[
-                ParamType::String,
-                ParamType::String,
-                ParamType::Address,
-                ParamType::Uint(256),
-                ParamType::Uint(256),
-                ParamType::String,
-                ParamType::String,
+                address(to),
+                string("HQ"),
+                string(location_id),
+                uint(expires_at),
+                string("guest"),
+                string("alice.dot"),
             ],
-            &metadata_bytes,
-        ) {
-            if let Token::Uint(expires_at) = &metadata[4] {
-                let expires = expires_at.as_u64();
-                if expires > 0 && block_timestamp() > expires {
-                    let result = encode(&[Token::Bool(false)]);
-                    api::return_value(ReturnFlags::empty(), &result);
-                    return;
-                }
-            }
+        )
+    }
+
+    #[test]
+    fn deployer_holds_every_role() {
+        setup();
+        assert_eq!(query::<[u8; 20]>("owner()", &[]), DEPLOYER);
+        for role in [DEFAULT_ADMIN_ROLE, ADMIN_ROLE, MINTER_ROLE, REVOKER_ROLE] {
+            let args = [bytes32(role), address(DEPLOYER)];
+            assert!(query::<bool>("hasRole(bytes32,address)", &args));
         }
+        assert_eq!(query::<U256>("totalMinted()", &[]), 0.into());
     }
 
-    let result = encode(&[Token::Bool(true)]);
-    api::return_value(ReturnFlags::empty(), &result);
-}
+    #[test]
+    fn mint_and_read_a_pass() {
+        setup();
+        mock::set_caller(ALICE);
+        assert_eq!(mint(ALICE, "hq-1", 0).data, encode(&[uint(1)]));
+        let topics: Vec<_> = mock::take_events().iter().map(|event| event.topics[0]).collect();
+        assert_eq!(topics, [Transfer::TOPIC, AccessPassMinted::TOPIC]);
+
+        assert_eq!(query::<[u8; 20]>("ownerOf(uint256)", &[uint(1)]), ALICE);
+        assert_eq!(query::<U256>("balanceOf(address)", &[address(ALICE)]), 1.into());
+        assert!(query::<bool>("isPassValid(uint256)", &[uint(1)]));
+        let metadata: PassMetadata = query("getPassMetadata(uint256)", &[uint(1)]);
+        assert_eq!((metadata.1.as_str(), metadata.2, metadata.3), ("hq-1", ALICE, NOW.into()));
+        assert_eq!(query::<Vec<U256>>("getPassesByHolder(address)", &[address(ALICE)]), [1.into()]);
+        assert_eq!(query::<Vec<U256>>("getPassesByLocation(string)", &[string("hq-1")]), [1.into()]);
+    }
 
-/// Get access pass metadata
-fn get_pass_metadata(data: &[u8]) {
-    let tokens = match decode(&[ParamType::Uint(256)], data) {
-        Ok(t) => t,
-        Err(_) => {
-            api::return_value(ReturnFlags::REVERT, b"DECODE_ERROR");
-            return;
-        }
-    };
+    #[test]
+    fn minting_for_others_needs_the_minter_role() {
+        setup();
+        mock::set_caller(ALICE);
+        assert_eq!(mint(BOB, "hq-1", 0).data, Unauthorized {}.encode());
+
+        mock::set_caller(DEPLOYER);
+        call("grantMinterRole(address)", &[address(ALICE)]);
+        mock::set_caller(ALICE);
+        assert!(!mint(BOB, "hq-1", 0).reverted);
+        assert_eq!(mint(ALICE, "hq-1", NOW).data, InvalidExpiration {}.encode());
+    }
 
-    let token_id = match &tokens[0] {
-        Token::Uint(n) => n.as_u64(),
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"INVALID_TOKEN_ID");
-            return;
-        }
-    };
+    #[test]
+    fn revoked_and_expired_passes_are_invalid() {
+        setup();
+        mint(ALICE, "hq-1", NOW + 10);
+        mint(BOB, "hq-1", 0);
+
+        mock::set_caller(ALICE);
+        let out = call("revokeAccessPass(uint256)", &[uint(2)]);
+        assert_eq!(out.data, AccessControlUnauthorizedAccount { account: ALICE, needed_role: REVOKER_ROLE }.encode());
+
+        mock::set_caller(DEPLOYER);
+        assert!(!call("revokeAccessPass(uint256)", &[uint(2)]).reverted);
+        assert!(!query::<bool>("isPassValid(uint256)", &[uint(2)]));
+        assert_eq!(call("ownerOf(uint256)", &[uint(2)]).data, ERC721NonexistentToken { token_id: 2.into() }.encode());
+        assert_eq!(query::<U256>("getPassCountByLocation(string)", &[string("hq-1")]), 1.into());
+
+        assert!(query::<bool>("isPassValid(uint256)", &[uint(1)]));
+        mock::set_now(NOW + 11);
+        assert!(!query::<bool>("isPassValid(uint256)", &[uint(1)]));
+    }
 
-    // Check if token exists
-    if get_storage(&storage_key_owner(token_id)).is_none() {
-        api::return_value(ReturnFlags::REVERT, b"TOKEN_NOT_FOUND");
-        return;
+    #[test]
+    fn passes_are_soulbound() {
+        setup();
+        mint(ALICE, "hq-1", 0);
+        mock::set_caller(ALICE);
+        let out = call("transferFrom(address,address,uint256)", &[address(ALICE), address(BOB), uint(1)]);
+        assert_eq!(out.data, TransferNotAllowed {}.encode());
+        assert_eq!(query::<[u8; 20]>("ownerOf(uint256)", &[uint(1)]), ALICE);
     }
 
-    // Get metadata
-    if let Some(metadata_bytes) = get_storage(&storage_key_pass(token_id)) {
-        // Check if revoked
-        let revoked = get_storage(&storage_key_revoked(token_id)).is_some();
+    #[test]
+    fn call_data_is_read_whole_up_to_the_bound() {
+        setup();
+        // Mint with string fields filling the bound, then read the pass back
+        let text = "x".repeat((MAX_CALL_DATA_LEN - 4 - 10 * 32) / 4 / 32 * 32);
+        let args = [
+            address(ALICE),
+            Token::String(text.clone()),
+            Token::String(text.clone()),
+            uint(NOW + 100),
+            Token::String(text.clone()),
+            Token::String(text.clone()),
+        ];
+        assert!(encode(&args).len() + 4 <= MAX_CALL_DATA_LEN);
+        assert!(!call("mintAccessPass(address,string,string,uint256,string,string)", &args).reverted);
+        assert_eq!(query::<Vec<U256>>("getPassesByLocation(string)", &[Token::String(text)]), [U256::from(1)]);
+
+        let mut input = selector("name()").to_vec();
+        input.resize(MAX_CALL_DATA_LEN + 1, 0);
+        assert_eq!(mock::execute(dispatch, &input), mock::Outcome { reverted: true, data: Vec::new() });
+    }
 
-        // Decode and re-encode with revoked flag
-        if let Ok(mut metadata) = decode(
-            &[
-                ParamType::String,
-                ParamType::String,
-                ParamType::Address,
-                ParamType::Uint(256),
-                ParamType::Uint(256),
-                ParamType::String,
-                ParamType::String,
-            ],
-            &metadata_bytes,
-        ) {
-            // Return as tuple
-            let result = encode(&[Token::Tuple(vec![
-                metadata[0].clone(), // location
-                metadata[1].clone(), // locationId
-                metadata[2].clone(), // holder
-                metadata[3].clone(), // issuedAt
-                metadata[4].clone(), // expiresAt
-                metadata[5].clone(), // accessLevel
-                Token::Bool(revoked), // revoked
-                metadata[6].clone(), // identityDisplay
-            ])]);
-
-            api::return_value(ReturnFlags::empty(), &result);
-            return;
+    /// An address as an indexed event topic
+    fn topic(account: [u8; 20]) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[12..].copy_from_slice(&account);
+        word
+    }
+
+    fn grant_role(role: [u8; 32], account: [u8; 20]) -> mock::Outcome {
+        call("grantRole(bytes32,address)", &[bytes32(role), address(account)])
+    }
+
+    fn has_role(role: [u8; 32], account: [u8; 20]) -> bool {
+        query("hasRole(bytes32,address)", &[bytes32(role), address(account)])
+    }
+
+    #[test]
+    fn only_the_role_admin_grants_and_revokes() {
+        setup();
+        mock::set_caller(ALICE);
+        let unauthorized = AccessControlUnauthorizedAccount { account: ALICE, needed_role: ADMIN_ROLE };
+        assert_eq!(grant_role(MINTER_ROLE, BOB).data, unauthorized.encode());
+
+        // ADMIN_ROLE is administered by the owner
+        mock::set_caller(DEPLOYER);
+        assert!(!grant_role(ADMIN_ROLE, ALICE).reverted);
+        let events = mock::take_events();
+        assert_eq!(events[0].topics, [RoleGranted::TOPIC, ADMIN_ROLE, topic(ALICE), topic(DEPLOYER)]);
+
+        mock::set_caller(ALICE);
+        assert!(!grant_role(MINTER_ROLE, BOB).reverted);
+        assert!(has_role(MINTER_ROLE, BOB));
+        let unauthorized = AccessControlUnauthorizedAccount { account: ALICE, needed_role: DEFAULT_ADMIN_ROLE };
+        assert_eq!(grant_role(ADMIN_ROLE, BOB).data, unauthorized.encode());
+        mock::take_events();
+
+        let out = call("revokeRole(bytes32,address)", &[bytes32(MINTER_ROLE), address(BOB)]);
+        assert!(!out.reverted);
+        assert!(!has_role(MINTER_ROLE, BOB));
+        let events = mock::take_events();
+        assert_eq!(events[0].topics, [RoleRevoked::TOPIC, MINTER_ROLE, topic(BOB), topic(ALICE)]);
+
+        mock::set_caller(BOB);
+        let out = call("revokeRole(bytes32,address)", &[bytes32(ADMIN_ROLE), address(ALICE)]);
+        let unauthorized = AccessControlUnauthorizedAccount { account: BOB, needed_role: DEFAULT_ADMIN_ROLE };
+        assert_eq!(out.data, unauthorized.encode());
+        assert!(has_role(ADMIN_ROLE, ALICE));
+    }
+
+    #[test]
+    fn roles_are_renounced_only_by_their_holder() {
+        setup();
+        grant_role(MINTER_ROLE, BOB);
+        mock::take_events();
+
+        mock::set_caller(ALICE);
+        let out = call("renounceRole(bytes32,address)", &[bytes32(MINTER_ROLE), address(BOB)]);
+        assert_eq!(out.data, AccessControlBadConfirmation {}.encode());
+        assert!(has_role(MINTER_ROLE, BOB));
+
+        mock::set_caller(BOB);
+        assert!(!call("renounceRole(bytes32,address)", &[bytes32(MINTER_ROLE), address(BOB)]).reverted);
+        assert!(!has_role(MINTER_ROLE, BOB));
+        let events = mock::take_events();
+        assert_eq!(events[0].topics, [RoleRevoked::TOPIC, MINTER_ROLE, topic(BOB), topic(BOB)]);
+    }
+
+    #[test]
+    fn ownership_moves_with_the_default_admin_role() {
+        setup();
+        mock::set_caller(ALICE);
+        let out = call("transferOwnership(address)", &[address(ALICE)]);
+        assert_eq!(out.data, OwnableUnauthorizedAccount { account: ALICE }.encode());
+
+        mock::set_caller(DEPLOYER);
+        let out = call("transferOwnership(address)", &[address([0; 20])]);
+        assert_eq!(out.data, OwnableInvalidOwner { owner: [0; 20] }.encode());
+
+        assert!(!call("transferOwnership(address)", &[address(ALICE)]).reverted);
+        let topics: Vec<_> = mock::take_events().into_iter().map(|event| event.topics).collect();
+        assert_eq!(
+            topics,
+            [
+                vec![RoleRevoked::TOPIC, DEFAULT_ADMIN_ROLE, topic(DEPLOYER), topic(DEPLOYER)],
+                vec![RoleGranted::TOPIC, DEFAULT_ADMIN_ROLE, topic(ALICE), topic(DEPLOYER)],
+                vec![OwnershipTransferred::TOPIC, topic(DEPLOYER), topic(ALICE)],
+            ]
+        );
+        assert_eq!(query::<[u8; 20]>("owner()", &[]), ALICE);
+        assert!(has_role(DEFAULT_ADMIN_ROLE, ALICE));
+        assert!(!has_role(DEFAULT_ADMIN_ROLE, DEPLOYER));
+
+        // The previous owner keeps ADMIN_ROLE but nothing the owner administers
+        let unauthorized = AccessControlUnauthorizedAccount { account: DEPLOYER, needed_role: DEFAULT_ADMIN_ROLE };
+        assert_eq!(grant_role(ADMIN_ROLE, BOB).data, unauthorized.encode());
+        let out = call("transferOwnership(address)", &[address(DEPLOYER)]);
+        assert_eq!(out.data, OwnableUnauthorizedAccount { account: DEPLOYER }.encode());
+
+        mock::set_caller(ALICE);
+        assert!(!grant_role(ADMIN_ROLE, BOB).reverted);
+    }
+
+    fn passes_by_holder(holder: [u8; 20], offset: u64, limit: U256) -> Vec<U256> {
+        query("getPassesByHolder(address,uint256,uint256)", &[address(holder), uint(offset), Token::Uint(limit)])
+    }
+
+    fn passes_by_location(location_id: &str, offset: u64, limit: U256) -> Vec<U256> {
+        query("getPassesByLocation(string,uint256,uint256)", &[string(location_id), uint(offset), Token::Uint(limit)])
+    }
+
+    #[test]
+    fn pages_slice_the_index() {
+        setup();
+        for _ in 0..5 {
+            mint(ALICE, "hq-1", 0);
         }
+        let ids = |ids: &[u64]| ids.iter().map(|id| U256::from(*id)).collect::<Vec<_>>();
+        assert_eq!(passes_by_holder(ALICE, 1, 2.into()), ids(&[2, 3]));
+        assert_eq!(passes_by_holder(ALICE, 3, 10.into()), ids(&[4, 5]));
+        assert_eq!(passes_by_location("hq-1", 0, 0.into()), ids(&[]));
+        // Offsets past the end, however large, give an empty page
+        assert_eq!(passes_by_location("hq-1", 5, 1.into()), ids(&[]));
+        let out = call("getPassesByHolder(address,uint256,uint256)", &[address(ALICE), Token::Uint(U256::MAX), uint(1)]);
+        assert_eq!(out.data, encode(&[Token::Array(Vec::new())]));
+        assert_eq!(passes_by_holder(BOB, 0, 10.into()), ids(&[]));
     }
 
-    api::return_value(ReturnFlags::REVERT, b"METADATA_ERROR");
-}
+    #[test]
+    fn pages_are_capped() {
+        setup();
+        let count = MAX_PAGE + 3;
+        for _ in 0..count {
+            mint(ALICE, "hq-1", 0);
+        }
+        // `call` checks each of these fits the heap
+        let first = query::<Vec<U256>>("getPassesByLocation(string)", &[string("hq-1")]);
+        assert_eq!(first.len() as u64, MAX_PAGE);
+        assert_eq!(query::<Vec<U256>>("getPassesByHolder(address)", &[address(ALICE)]), first);
+        assert_eq!(passes_by_location("hq-1", 0, U256::MAX), first);
+        assert_eq!(passes_by_holder(ALICE, MAX_PAGE, U256::MAX), [(MAX_PAGE + 1).into(), (MAX_PAGE + 2).into(), count.into()]);
+    }
 
-/// Get total number of minted passes
-fn total_minted() {
-    let count = get_u64(&TOKEN_COUNTER_KEY).saturating_sub(1);
-    let result = encode(&[Token::Uint(count.into())]);
-    api::return_value(ReturnFlags::empty(), &result);
+    #[test]
+    fn revoking_moves_the_last_pass_into_the_gap() {
+        setup();
+        for _ in 0..4 {
+            mint(ALICE, "hq-1", 0);
+        }
+        let revoke = |token_id: u64| assert!(!call("revokeAccessPass(uint256)", &[uint(token_id)]).reverted);
+        let ids = |ids: &[u64]| ids.iter().map(|id| U256::from(*id)).collect::<Vec<_>>();
+        let holder = || passes_by_holder(ALICE, 0, 10.into());
+        let location = || passes_by_location("hq-1", 0, 10.into());
+
+        revoke(2);
+        assert_eq!(holder(), ids(&[1, 4, 3]));
+        assert_eq!(location(), ids(&[1, 4, 3]));
+        // The moved pass kept track of its new position
+        revoke(4);
+        assert_eq!(holder(), ids(&[1, 3]));
+        assert_eq!(location(), ids(&[1, 3]));
+        revoke(1);
+        revoke(3);
+        assert_eq!((holder(), location()), (ids(&[]), ids(&[])));
+        assert_eq!(query::<U256>("balanceOf(address)", &[address(ALICE)]), 0.into());
+    }
 }
@@ -0,0 +1,64 @@
+//! Calls into other contracts and precompiles.
+//!
+//! Only read-only calls are supported: the callee runs with
+//! `CallFlags::READ_ONLY`, so it cannot change state or re-enter the caller
+//! with side effects.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use ethabi::{decode, Token};
+
+#[cfg(not(feature = "mock"))]
+use crate::host::HostFn;
+use crate::abi::{encode, SolType};
+use crate::host::{Api, CallFlags, ReturnErrorCode};
+
+/// Why a call to another contract did not produce a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The callee reverted with this data
+    Reverted(Vec<u8>),
+    /// The callee trapped or ran out of resources
+    Failed,
+    /// The return data did not decode as the expected type
+    Decode,
+}
+
+/// Call `callee` read-only with raw `input`, returning its raw output.
+///
+/// Calling an address without code succeeds with empty output.
+pub fn static_call(callee: &[u8; 20], input: &[u8]) -> Result<Vec<u8>, CallError> {
+    let result = Api::call(
+        CallFlags::READ_ONLY,
+        callee,
+        u64::MAX,       // all remaining ref_time
+        u64::MAX,       // all remaining proof_size
+        &[u8::MAX; 32], // no deposit limit
+        &[0u8; 32],     // no value
+        input,
+        None,
+    );
+
+    // Read the output back separately so its size needs no upfront bound
+    let mut output = vec![0u8; Api::return_data_size() as usize];
+    Api::return_data_copy(&mut &mut output[..], 0);
+
+    match result {
+        Ok(()) => Ok(output),
+        Err(ReturnErrorCode::CalleeReverted) => Err(CallError::Reverted(output)),
+        Err(_) => Err(CallError::Failed),
+    }
+}
+
+/// Call a view function `selector(args)` on `callee` and decode its single
+/// return value as `R`.
+pub fn call_view<R: SolType>(callee: &[u8; 20], selector: [u8; 4], args: &[Token]) -> Result<R, CallError> {
+    let mut input = selector.to_vec();
+    input.extend(encode(args));
+    let output = static_call(callee, &input)?;
+    decode(&[R::param_type()], &output)
+        .ok()
+        .and_then(|tokens| tokens.into_iter().next())
+        .and_then(R::from_token)
+        .ok_or(CallError::Decode)
+}
@@ -0,0 +1,307 @@
+//! Compact binary encoding for values kept in contract storage.
+//!
+//! Integers are fixed-width little-endian, fixed arrays are raw bytes and
+//! variable-length values carry a `u32` length prefix, so every encoding is
+//! prefix-free and can be used both as a storage value and as part of a
+//! hashed storage key. Structs get an impl through [`storage_struct!`].
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use ethabi::Uint as U256;
+
+/// Sink for encoded bytes.
+pub trait Output {
+    fn write(&mut self, bytes: &[u8]);
+
+    /// Hint that about `additional` more bytes are coming.
+    fn reserve(&mut self, _additional: usize) {}
+}
+
+impl Output for Vec<u8> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+
+    // Growing by doubling leaves every old buffer behind under a bump allocator
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
+}
+
+/// A type with a storage encoding.
+pub trait Codec: Sized {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O);
+
+    /// Decode from the front of `input`, advancing it past the value.
+    fn decode_from(input: &mut &[u8]) -> Option<Self>;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(&mut out);
+        out
+    }
+
+    /// Decode a complete value, rejecting trailing bytes.
+    fn decode(mut input: &[u8]) -> Option<Self> {
+        let value = Self::decode_from(&mut input)?;
+        input.is_empty().then_some(value)
+    }
+}
+
+/// Split `n` bytes off the front of `input`.
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Some(head)
+}
+
+macro_rules! impl_codec_int {
+    ($($ty:ty),*) => {$(
+        impl Codec for $ty {
+            fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+                out.write(&self.to_le_bytes());
+            }
+
+            fn decode_from(input: &mut &[u8]) -> Option<Self> {
+                let bytes = take(input, core::mem::size_of::<$ty>())?;
+                Some(<$ty>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+impl_codec_int!(u8, u16, u32, u64, u128);
+
+impl Codec for () {
+    fn encode_to<O: Output + ?Sized>(&self, _out: &mut O) {}
+
+    fn decode_from(_input: &mut &[u8]) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Codec for bool {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+        out.write(&[*self as u8]);
+    }
+
+    fn decode_from(input: &mut &[u8]) -> Option<Self> {
+        match take(input, 1)?[0] {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Codec for U256 {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+        let mut bytes = [0u8; 32];
+        self.to_little_endian(&mut bytes);
+        out.write(&bytes);
+    }
+
+    fn decode_from(input: &mut &[u8]) -> Option<Self> {
+        Some(U256::from_little_endian(take(input, 32)?))
+    }
+}
+
+impl<const N: usize> Codec for [u8; N] {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+        out.write(self);
+    }
+
+    fn decode_from(input: &mut &[u8]) -> Option<Self> {
+        take(input, N)?.try_into().ok()
+    }
+}
+
+impl<T: Codec> Codec for Vec<T> {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+        out.reserve(4 + self.len() * core::mem::size_of::<T>());
+        (self.len() as u32).encode_to(out);
+        for item in self {
+            item.encode_to(out);
+        }
+    }
+
+    fn decode_from(input: &mut &[u8]) -> Option<Self> {
+        let len = u32::decode_from(input)? as usize;
+        // Don't preallocate more than the input could possibly hold
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::decode_from(input)?);
+        }
+        Some(items)
+    }
+}
+
+impl Codec for String {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+        (self.len() as u32).encode_to(out);
+        out.write(self.as_bytes());
+    }
+
+    fn decode_from(input: &mut &[u8]) -> Option<Self> {
+        let len = u32::decode_from(input)? as usize;
+        String::from_utf8(take(input, len)?.to_vec()).ok()
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+        match self {
+            None => out.write(&[0]),
+            Some(value) => {
+                out.write(&[1]);
+                value.encode_to(out);
+            }
+        }
+    }
+
+    fn decode_from(input: &mut &[u8]) -> Option<Self> {
+        match take(input, 1)?[0] {
+            0 => Some(None),
+            1 => Some(Some(T::decode_from(input)?)),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_codec_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: Codec),+> Codec for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode_to<O: Output + ?Sized>(&self, out: &mut O) {
+                let ($($name,)+) = self;
+                $($name.encode_to(out);)+
+            }
+
+            fn decode_from(input: &mut &[u8]) -> Option<Self> {
+                Some(($($name::decode_from(input)?,)+))
+            }
+        }
+    };
+}
+
+impl_codec_tuple!(A);
+impl_codec_tuple!(A, B);
+impl_codec_tuple!(A, B, C);
+impl_codec_tuple!(A, B, C, D);
+
+/// Declare a struct and derive its [`Codec`](crate::codec::Codec) impl,
+/// encoding fields in declaration order:
+///
+/// ```ignore
+/// storage_struct! {
+///     #[derive(Clone)]
+///     pub struct Pass {
+///         pub holder: [u8; 20],
+///         pub location: String,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! storage_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $( $(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $( $(#[$field_meta])* $field_vis $field: $ty, )*
+        }
+
+        impl $crate::codec::Codec for $name {
+            fn encode_to<O: $crate::codec::Output + ?Sized>(&self, out: &mut O) {
+                $( $crate::codec::Codec::encode_to(&self.$field, out); )*
+            }
+
+            fn decode_from(input: &mut &[u8]) -> ::core::option::Option<Self> {
+                ::core::option::Option::Some($name {
+                    $( $field: <$ty as $crate::codec::Codec>::decode_from(input)?, )*
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    storage_struct! {
+        #[derive(Debug, PartialEq)]
+        struct Record {
+            id: u64,
+            owner: [u8; 20],
+            note: String,
+            tags: Vec<u8>,
+            parent: Option<u32>,
+        }
+    }
+
+    fn round_trip<T: Codec + PartialEq + core::fmt::Debug>(value: T) -> Vec<u8> {
+        let bytes = value.encode();
+        assert_eq!(T::decode(&bytes), Some(value));
+        bytes
+    }
+
+    #[test]
+    fn integers_are_little_endian() {
+        assert_eq!(round_trip(0x0102u16), [2, 1]);
+        assert_eq!(round_trip(7u64), [7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(round_trip(u128::MAX).len(), 16);
+        assert_eq!(round_trip(U256::from(1) << 255)[31], 0x80);
+        assert_eq!(round_trip(true), [1]);
+        assert_eq!(round_trip(()), []);
+    }
+
+    #[test]
+    fn variable_length_values_carry_a_length_prefix() {
+        assert_eq!(round_trip(String::from("hi")), [2, 0, 0, 0, b'h', b'i']);
+        assert_eq!(round_trip(vec![0u8, 0, 0]), [3, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(round_trip(Vec::<u64>::new()), [0, 0, 0, 0]);
+        assert_eq!(round_trip(vec![vec![1u8], vec![]]), [2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(round_trip([0u8; 4]), [0; 4]);
+        assert_eq!(round_trip(vec![0u8; 1000]).len(), 1004);
+    }
+
+    #[test]
+    fn options_tuples_and_structs_round_trip() {
+        assert_eq!(round_trip(None::<u8>), [0]);
+        assert_eq!(round_trip(Some(0u8)), [1, 0]);
+        round_trip((1u64, [2u8; 20], String::from("x"), Some(false)));
+        let record = Record { id: 9, owner: [3; 20], note: "n".into(), tags: vec![0, 0], parent: Some(4) };
+        let bytes = round_trip(record);
+        assert_eq!(bytes.len(), 8 + 20 + 5 + 6 + 5);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        // Truncated, with trailing bytes, or out of range
+        assert_eq!(u64::decode(&[1, 2, 3]), None);
+        assert_eq!(u8::decode(&[1, 0]), None);
+        assert_eq!(bool::decode(&[2]), None);
+        assert_eq!(Option::<u8>::decode(&[2, 0]), None);
+        assert_eq!(String::decode(&[3, 0, 0, 0, b'a']), None);
+        assert_eq!(String::decode(&[1, 0, 0, 0, 0xff]), None);
+        assert_eq!(Vec::<u8>::decode(&[0xff, 0xff, 0xff, 0xff]), None);
+    }
+
+    #[test]
+    fn decode_from_advances_past_the_value() {
+        let mut bytes = 5u32.encode();
+        bytes.extend(String::from("rest").encode());
+        let mut input = &bytes[..];
+        assert_eq!(u32::decode_from(&mut input), Some(5));
+        assert_eq!(String::decode_from(&mut input).as_deref(), Some("rest"));
+        assert!(input.is_empty());
+    }
+}
@@ -0,0 +1,420 @@
+//! Typed storage containers.
+//!
+//! Each container owns one namespace byte and derives its keys by hashing a
+//! tagged preimage, so no two containers (or two entries of one container)
+//! can share a key:
+//!
+//! ```text
+//! StorageValue        keccak256(VALUE ++ ns)
+//! StorageMap[k]       keccak256(MAP ++ ns ++ encode(k))
+//! StorageVec len      keccak256(VEC ++ ns)              (StorageVecMap: ++ encode(k))
+//! StorageVec[i]       keccak256(VEC_ITEM ++ len_key ++ i)
+//! ```
+//!
+//! Declare a contract's containers with [`storage_layout!`], which rejects
+//! duplicate namespaces at compile time.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::codec::{Codec, Output};
+use crate::keccak;
+use crate::storage::{self, KEY_TAG_MAP, KEY_TAG_VALUE, KEY_TAG_VEC, KEY_TAG_VEC_ITEM};
+
+/// Builds a hashed key preimage on the stack, spilling to the heap only for
+/// long keys such as strings.
+struct KeyBuilder {
+    buf: [u8; 64],
+    len: usize,
+    spill: Vec<u8>,
+}
+
+impl KeyBuilder {
+    fn new(tag: u8, namespace: u8) -> Self {
+        let mut builder = KeyBuilder { buf: [0u8; 64], len: 0, spill: Vec::new() };
+        builder.write(&[tag, namespace]);
+        builder
+    }
+
+    fn finish(self) -> [u8; 32] {
+        if self.spill.is_empty() {
+            keccak::hash(&self.buf[..self.len])
+        } else {
+            keccak::hash(&self.spill)
+        }
+    }
+}
+
+impl Output for KeyBuilder {
+    fn write(&mut self, bytes: &[u8]) {
+        if self.spill.is_empty() && self.len + bytes.len() <= self.buf.len() {
+            self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        } else {
+            if self.spill.is_empty() {
+                self.spill.extend_from_slice(&self.buf[..self.len]);
+            }
+            self.spill.extend_from_slice(bytes);
+        }
+    }
+}
+
+fn get<T: Codec>(key: &[u8; 32]) -> Option<T> {
+    storage::get_storage(key).and_then(|bytes| T::decode(&bytes))
+}
+
+fn set<T: Codec>(key: &[u8; 32], value: &T) {
+    storage::set_storage(key, &value.encode());
+}
+
+// ============ StorageValue ============
+
+/// A single value.
+pub struct StorageValue<T> {
+    key: [u8; 32],
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Codec> StorageValue<T> {
+    pub const fn new(namespace: u8) -> Self {
+        StorageValue { key: keccak::keccak256(&[KEY_TAG_VALUE, namespace]), _marker: PhantomData }
+    }
+
+    pub fn get(&self) -> Option<T> {
+        get(&self.key)
+    }
+
+    pub fn get_or_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.get().unwrap_or_default()
+    }
+
+    pub fn set(&self, value: &T) {
+        set(&self.key, value)
+    }
+
+    pub fn exists(&self) -> bool {
+        storage::contains(&self.key)
+    }
+
+    pub fn clear(&self) {
+        storage::clear_storage(&self.key)
+    }
+}
+
+// ============ StorageMap ============
+
+/// A key-value mapping.
+pub struct StorageMap<K, V> {
+    namespace: u8,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K: Codec, V: Codec> StorageMap<K, V> {
+    pub const fn new(namespace: u8) -> Self {
+        StorageMap { namespace, _marker: PhantomData }
+    }
+
+    fn key(&self, key: &K) -> [u8; 32] {
+        let mut builder = KeyBuilder::new(KEY_TAG_MAP, self.namespace);
+        key.encode_to(&mut builder);
+        builder.finish()
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        get(&self.key(key))
+    }
+
+    pub fn get_or_default(&self, key: &K) -> V
+    where
+        V: Default,
+    {
+        self.get(key).unwrap_or_default()
+    }
+
+    pub fn set(&self, key: &K, value: &V) {
+        set(&self.key(key), value)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        storage::contains(&self.key(key))
+    }
+
+    pub fn remove(&self, key: &K) {
+        storage::clear_storage(&self.key(key))
+    }
+}
+
+// ============ StorageVec ============
+
+/// A list with indexed access, push, pop and swap-remove.
+pub struct StorageVec<T> {
+    len_key: [u8; 32],
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Codec> StorageVec<T> {
+    pub const fn new(namespace: u8) -> Self {
+        StorageVec { len_key: keccak::keccak256(&[KEY_TAG_VEC, namespace]), _marker: PhantomData }
+    }
+
+    fn item_key(&self, index: u64) -> [u8; 32] {
+        let mut preimage = [0u8; 41];
+        preimage[0] = KEY_TAG_VEC_ITEM;
+        preimage[1..33].copy_from_slice(&self.len_key);
+        preimage[33..].copy_from_slice(&index.to_le_bytes());
+        keccak::hash(&preimage)
+    }
+
+    pub fn len(&self) -> u64 {
+        storage::get_u64(&self.len_key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn set_len(&self, len: u64) {
+        storage::set_u64(&self.len_key, len)
+    }
+
+    pub fn get(&self, index: u64) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        get(&self.item_key(index))
+    }
+
+    /// Append `value`, returning its index.
+    pub fn push(&self, value: &T) -> u64 {
+        let index = self.len();
+        set(&self.item_key(index), value);
+        self.set_len(index + 1);
+        index
+    }
+
+    /// Overwrite an existing element; returns `false` if out of bounds.
+    pub fn set(&self, index: u64, value: &T) -> bool {
+        if index >= self.len() {
+            return false;
+        }
+        set(&self.item_key(index), value);
+        true
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let key = self.item_key(len - 1);
+        let value = get(&key);
+        storage::clear_storage(&key);
+        self.set_len(len - 1);
+        value
+    }
+
+    /// Remove the element at `index` by moving the last element into its
+    /// place. Order is not preserved.
+    pub fn swap_remove(&self, index: u64) -> Option<T> {
+        let len = self.len();
+        if index >= len {
+            return None;
+        }
+        let removed = get(&self.item_key(index));
+        let last = self.pop();
+        if index != len - 1 {
+            if let Some(last) = &last {
+                set(&self.item_key(index), last);
+            }
+        }
+        removed
+    }
+
+    /// Remove every element; costs one storage write per element.
+    pub fn clear(&self) {
+        for index in 0..self.len() {
+            storage::clear_storage(&self.item_key(index));
+        }
+        storage::clear_storage(&self.len_key);
+    }
+
+    /// Up to `limit` elements starting at `offset`; empty past the end.
+    pub fn page(&self, offset: u64, limit: u64) -> Vec<T> {
+        let len = self.len();
+        let start = offset.min(len);
+        let end = start.saturating_add(limit).min(len);
+        (start..end).filter_map(|index| get(&self.item_key(index))).collect()
+    }
+}
+
+// ============ StorageVecMap ============
+
+/// A [`StorageVec`] per key.
+pub struct StorageVecMap<K, T> {
+    namespace: u8,
+    _marker: PhantomData<fn() -> (K, T)>,
+}
+
+impl<K: Codec, T: Codec> StorageVecMap<K, T> {
+    pub const fn new(namespace: u8) -> Self {
+        StorageVecMap { namespace, _marker: PhantomData }
+    }
+
+    pub fn at(&self, key: &K) -> StorageVec<T> {
+        let mut builder = KeyBuilder::new(KEY_TAG_VEC, self.namespace);
+        key.encode_to(&mut builder);
+        StorageVec { len_key: builder.finish(), _marker: PhantomData }
+    }
+}
+
+/// Declare a contract's storage containers, one namespace byte each.
+///
+/// Namespaces live in a `#[repr(u8)]` enum, so reusing a byte is a compile
+/// error (E0081) instead of a silent key collision:
+///
+/// ```ignore
+/// storage_layout! {
+///     FORM_COUNT: StorageValue<u64> = 0x01;
+///     FORM_CREATOR: StorageMap<u64, [u8; 20]> = 0x02;
+///     RESPONSES: StorageVecMap<u64, Vec<u8>> = 0x03;
+/// }
+///
+/// let form_id = FORM_COUNT.get_or_default() + 1;
+/// FORM_CREATOR.set(&form_id, &caller);
+/// ```
+#[macro_export]
+macro_rules! storage_layout {
+    (
+        $( $(#[$meta:meta])* $name:ident: $kind:ident<$($ty:ty),+> = $namespace:literal; )*
+    ) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[repr(u8)]
+        enum StorageNamespace {
+            $( $name = $namespace, )*
+        }
+
+        $(
+            $(#[$meta])*
+            const $name: $crate::collections::$kind<$($ty),+> =
+                $crate::collections::$kind::new(StorageNamespace::$name as u8);
+        )*
+    };
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use alloc::string::String;
+    use alloc::vec;
+
+    const NS: u8 = 0x05;
+
+    #[test]
+    fn keys_follow_the_documented_layout() {
+        let value = StorageValue::<u64>::new(NS);
+        let map = StorageMap::<u64, u64>::new(NS);
+        let vec = StorageVec::<u64>::new(NS);
+        let vec_map = StorageVecMap::<u64, u64>::new(NS);
+
+        assert_eq!(value.key, keccak::hash(&[KEY_TAG_VALUE, NS]));
+        let mut preimage = vec![KEY_TAG_MAP, NS];
+        preimage.extend(3u64.to_le_bytes());
+        assert_eq!(map.key(&3), keccak::hash(&preimage));
+        assert_eq!(vec.len_key, keccak::hash(&[KEY_TAG_VEC, NS]));
+        preimage[0] = KEY_TAG_VEC;
+        assert_eq!(vec_map.at(&3).len_key, keccak::hash(&preimage));
+
+        let mut preimage = vec![KEY_TAG_VEC_ITEM];
+        preimage.extend(vec.len_key);
+        preimage.extend(2u64.to_le_bytes());
+        assert_eq!(vec.item_key(2), keccak::hash(&preimage));
+    }
+
+    #[test]
+    fn long_map_keys_hash_the_whole_preimage() {
+        let map = StorageMap::<String, u64>::new(NS);
+        let key = String::from_utf8(vec![b'k'; 100]).unwrap();
+        let mut preimage = vec![KEY_TAG_MAP, NS];
+        preimage.extend(key.encode());
+        assert_eq!(map.key(&key), keccak::hash(&preimage));
+    }
+
+    #[test]
+    fn containers_sharing_a_namespace_do_not_collide() {
+        mock::reset();
+        let value = StorageValue::<u64>::new(NS);
+        let map = StorageMap::<u64, u64>::new(NS);
+        let vec = StorageVec::<u64>::new(NS);
+        let vec_map = StorageVecMap::<u64, u64>::new(NS);
+
+        value.set(&1);
+        map.set(&0, &2);
+        vec.push(&3);
+        vec_map.at(&0).push(&4);
+        vec_map.at(&0).push(&5);
+        vec_map.at(&1).push(&6);
+
+        assert_eq!(value.get(), Some(1));
+        assert_eq!(map.get(&0), Some(2));
+        assert_eq!((vec.len(), vec.get(0)), (1, Some(3)));
+        assert_eq!(vec_map.at(&0).page(0, 10), [4, 5]);
+        assert_eq!(vec_map.at(&1).page(0, 10), [6]);
+        assert_eq!(map.get(&1), None);
+        assert!(vec_map.at(&2).is_empty());
+    }
+
+    #[test]
+    fn namespaces_and_map_keys_are_separate() {
+        mock::reset();
+        let a = StorageMap::<(u64, u8), u64>::new(NS);
+        let b = StorageMap::<(u64, u8), u64>::new(NS + 1);
+        a.set(&(1, 2), &10);
+        b.set(&(1, 2), &20);
+        a.set(&(2, 1), &30);
+        assert_eq!((a.get(&(1, 2)), b.get(&(1, 2)), a.get(&(2, 1))), (Some(10), Some(20), Some(30)));
+
+        a.remove(&(1, 2));
+        assert!(!a.contains(&(1, 2)));
+        assert!(b.contains(&(1, 2)));
+    }
+
+    #[test]
+    fn vec_operations() {
+        mock::reset();
+        let vec = StorageVec::<String>::new(NS);
+        for item in ["a", "b", "c", "d"] {
+            vec.push(&item.into());
+        }
+        assert!(vec.set(1, &"B".into()));
+        assert!(!vec.set(4, &"x".into()));
+        assert_eq!(vec.get(4), None);
+        assert_eq!(vec.page(1, 2), ["B", "c"]);
+        assert_eq!(vec.page(3, 10), ["d"]);
+        assert!(vec.page(9, u64::MAX).is_empty());
+
+        assert_eq!(vec.swap_remove(0).as_deref(), Some("a"));
+        assert_eq!(vec.page(0, 10), ["d", "B", "c"]);
+        assert_eq!(vec.swap_remove(2).as_deref(), Some("c"));
+        assert_eq!(vec.pop().as_deref(), Some("B"));
+        assert_eq!(vec.page(0, 10), ["d"]);
+        assert_eq!(mock::storage(&vec.item_key(1)), None);
+    }
+
+    #[test]
+    fn clear_removes_every_slot() {
+        mock::reset();
+        let vec = StorageVec::<u64>::new(NS);
+        for item in 0..3 {
+            vec.push(&item);
+        }
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(mock::storage(&vec.len_key), None);
+        assert!((0..3).all(|index| mock::storage(&vec.item_key(index)).is_none()));
+        assert_eq!(vec.pop(), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Solidity-compatible revert data.
+//!
+//! Reverts carry the same bytes a Solidity contract would return, so
+//! viem/ethers decode them like any other contract error: `require(cond,
+//! "reason")` becomes [`revert`], a failed assertion or out-of-bounds index
+//! becomes [`panic`], and `revert CustomError(args)` is declared with
+//! [`sol_error!`].
+
+use alloc::vec::Vec;
+use ethabi::Token;
+
+#[cfg(not(feature = "mock"))]
+use crate::host::HostFn;
+use crate::abi::{encode, selector};
+use crate::host::{Api, ReturnFlags};
+
+/// Selector of `Error(string)`, the encoding of `require`/`revert("...")`
+pub const ERROR_SELECTOR: [u8; 4] = selector("Error(string)");
+/// Selector of `Panic(uint256)`
+pub const PANIC_SELECTOR: [u8; 4] = selector("Panic(uint256)");
+
+// Panic code (see the Solidity docs, "Panic via assert and Error via require")
+pub const PANIC_ARRAY_OUT_OF_BOUNDS: u8 = 0x32;
+
+/// Revert with raw return data.
+pub fn revert_with(data: &[u8]) -> ! {
+    Api::return_value(ReturnFlags::REVERT, data)
+}
+
+/// Encoded revert data: a 4-byte selector followed by ABI-encoded arguments.
+pub type RevertData = Vec<u8>;
+
+/// Return data of the error with `selector` and arguments `args`.
+pub fn encode_error(selector: [u8; 4], args: &[Token]) -> RevertData {
+    let mut data = selector.to_vec();
+    data.extend(encode(args));
+    data
+}
+
+/// `Error(string)` return data.
+pub fn error_string(reason: &str) -> RevertData {
+    encode_error(ERROR_SELECTOR, &[Token::String(reason.into())])
+}
+
+/// `revert(reason)`, as from a failed `require(cond, reason)`.
+pub fn revert(reason: &str) -> ! {
+    revert_with(&error_string(reason))
+}
+
+/// `Panic(code)`, as from a failed `assert` or an out-of-bounds index.
+pub fn panic(code: u8) -> ! {
+    revert_with(&encode_error(PANIC_SELECTOR, &[Token::Uint(code.into())]))
+}
+
+/// Declare a Solidity custom error as a struct with `encode()` and
+/// `revert()` methods.
+///
+/// The signature is checked against the field types at compile time, and the
+/// return data is the 4-byte selector followed by the ABI-encoded fields:
+///
+/// ```ignore
+/// sol_error! {
+///     #[signature("DeadlinePassed(uint64,uint64)")]
+///     pub struct DeadlinePassed {
+///         form_id: u64,
+///         deadline: u64,
+///     }
+/// }
+///
+/// DeadlinePassed { form_id, deadline }.revert();
+/// ```
+#[macro_export]
+macro_rules! sol_error {
+    (
+        #[signature($sig:literal)]
+        $vis:vis struct $name:ident {
+            $( $field:ident: $ty:ty ),* $(,)?
+        }
+    ) => {
+        $vis struct $name {
+            $( pub $field: $ty, )*
+        }
+
+        impl $name {
+            pub const SIGNATURE: &'static str = $sig;
+            pub const SELECTOR: [u8; 4] = $crate::abi::selector($sig);
+
+            const _CHECK: () = ::core::assert!(
+                $crate::abi::signature_matches($sig, &[$(<$ty as $crate::abi::SolType>::SOL_NAME),*]),
+                ::core::concat!("error signature does not match field types: ", $sig),
+            );
+
+            pub fn encode(self) -> $crate::errors::RevertData {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::_CHECK;
+                $crate::errors::encode_error(
+                    Self::SELECTOR,
+                    &[$($crate::abi::SolType::into_token(self.$field)),*],
+                )
+            }
+
+            pub fn revert(self) -> ! {
+                $crate::errors::revert_with(&self.encode())
+            }
+        }
+    };
+}
@@ -0,0 +1,208 @@
+//! Solidity-compatible event emission.
+//!
+//! topic0 is keccak256 of the event signature and `indexed` parameters are
+//! moved into topics as the Solidity ABI specifies, so logs are byte-identical
+//! to the equivalent Solidity `emit` and match ethers/viem event filters.
+
+use alloc::vec::Vec;
+use ethabi::Token;
+
+#[cfg(not(feature = "mock"))]
+use crate::host::HostFn;
+use crate::host::Api;
+use crate::abi::encode;
+use crate::keccak;
+
+/// Topic value of an indexed parameter.
+///
+/// Static types are their 32-byte ABI word; `string`/`bytes` are hashed
+/// as-is and arrays/tuples are hashed over their in-place encoding.
+pub fn topic(token: &Token) -> [u8; 32] {
+    match token {
+        Token::String(s) => keccak::hash(s.as_bytes()),
+        Token::Bytes(b) => keccak::hash(b),
+        Token::Array(_) | Token::FixedArray(_) | Token::Tuple(_) => keccak::hash(&in_place(token)),
+        _ => {
+            let mut word = [0u8; 32];
+            word.copy_from_slice(&encode(core::slice::from_ref(token)));
+            word
+        }
+    }
+}
+
+/// In-place encoding of a nested value for topic hashing: elements are
+/// concatenated without offsets or lengths and padded to 32 bytes.
+fn in_place(token: &Token) -> Vec<u8> {
+    match token {
+        Token::String(s) => padded(s.as_bytes()),
+        Token::Bytes(b) => padded(b),
+        Token::Array(items) | Token::FixedArray(items) | Token::Tuple(items) => {
+            items.iter().flat_map(in_place).collect()
+        }
+        _ => encode(core::slice::from_ref(token)),
+    }
+}
+
+fn padded(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    out.resize(bytes.len().div_ceil(32) * 32, 0);
+    out
+}
+
+/// Emit an event from its topic0 and `(value, indexed)` parameters in
+/// declaration order.
+pub fn emit<const N: usize>(topic0: [u8; 32], params: [(Token, bool); N]) {
+    let mut topics = [[0u8; 32]; 4];
+    topics[0] = topic0;
+    let mut topic_count = 1;
+    let mut data = Vec::with_capacity(N);
+    for (token, indexed) in params {
+        if indexed {
+            topics[topic_count] = topic(&token);
+            topic_count += 1;
+        } else {
+            data.push(token);
+        }
+    }
+    Api::deposit_event(&topics[..topic_count], &encode(&data));
+}
+
+/// Number of `#[indexed]` markers among a field's attribute names.
+#[doc(hidden)]
+pub const fn count_indexed(attrs: &[&str]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < attrs.len() {
+        let attr = attrs[i].as_bytes();
+        let expected = b"indexed";
+        assert!(attr.len() == expected.len(), "unknown event field attribute; expected #[indexed]");
+        let mut j = 0;
+        while j < expected.len() {
+            assert!(attr[j] == expected[j], "unknown event field attribute; expected #[indexed]");
+            j += 1;
+        }
+        count += 1;
+        i += 1;
+    }
+    count
+}
+
+/// Declare a typed event struct with an `emit()` method.
+///
+/// The signature is checked against the field types at compile time, and at
+/// most three fields may be `#[indexed]`:
+///
+/// ```ignore
+/// sol_event! {
+///     #[signature("AccessPassRevoked(uint256,address,uint256)")]
+///     pub struct AccessPassRevoked {
+///         #[indexed] token_id: U256,
+///         #[indexed] revoked_by: [u8; 20],
+///         timestamp: U256,
+///     }
+/// }
+///
+/// AccessPassRevoked { token_id, revoked_by, timestamp }.emit();
+/// ```
+#[macro_export]
+macro_rules! sol_event {
+    (
+        #[signature($sig:literal)]
+        $vis:vis struct $name:ident {
+            $( $(#[$attr:ident])? $field:ident: $ty:ty ),* $(,)?
+        }
+    ) => {
+        $vis struct $name {
+            $( pub $field: $ty, )*
+        }
+
+        impl $name {
+            pub const SIGNATURE: &'static str = $sig;
+            pub const TOPIC: [u8; 32] = $crate::keccak::keccak256($sig.as_bytes());
+
+            const _CHECK: () = {
+                ::core::assert!(
+                    $crate::abi::signature_matches($sig, &[$(<$ty as $crate::abi::SolType>::SOL_NAME),*]),
+                    ::core::concat!("event signature does not match field types: ", $sig),
+                );
+                let indexed = 0 $( + $crate::events::count_indexed(&[$(::core::stringify!($attr))?]) )*;
+                ::core::assert!(indexed <= 3, "an event can have at most three indexed fields");
+            };
+
+            pub fn emit(self) {
+                #[allow(clippy::let_unit_value)]
+                let () = Self::_CHECK;
+                $crate::events::emit(
+                    Self::TOPIC,
+                    [$((
+                        $crate::abi::SolType::into_token(self.$field),
+                        $crate::events::count_indexed(&[$(::core::stringify!($attr))?]) == 1,
+                    )),*],
+                );
+            }
+        }
+    };
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use alloc::string::String;
+    use alloc::vec;
+    use ethabi::Uint as U256;
+
+    crate::sol_event! {
+        #[signature("Mixed(uint256,string,address,string,bool)")]
+        struct Mixed {
+            #[indexed] id: U256,
+            label: String,
+            #[indexed] who: [u8; 20],
+            #[indexed] name: String,
+            flag: bool,
+        }
+    }
+
+    fn word(token: Token) -> [u8; 32] {
+        ethabi::encode(&[token]).try_into().unwrap()
+    }
+
+    #[test]
+    fn topic0_is_the_signature_hash() {
+        assert_eq!(Mixed::SIGNATURE, "Mixed(uint256,string,address,string,bool)");
+        assert_eq!(Mixed::TOPIC, keccak::hash(Mixed::SIGNATURE.as_bytes()));
+    }
+
+    #[test]
+    fn indexed_fields_become_topics_in_declaration_order() {
+        mock::reset();
+        Mixed { id: 7.into(), label: "hello".into(), who: [0x22; 20], name: "hq-1".into(), flag: true }.emit();
+
+        let events = mock::take_events();
+        assert_eq!(events.len(), 1);
+        let expected = [
+            Mixed::TOPIC,
+            word(Token::Uint(7.into())),
+            word(Token::Address([0x22; 20].into())),
+            // Dynamic values are hashed
+            keccak::hash(b"hq-1"),
+        ];
+        assert_eq!(events[0].topics, expected);
+        assert_eq!(events[0].data, ethabi::encode(&[Token::String("hello".into()), Token::Bool(true)]));
+    }
+
+    #[test]
+    fn composite_topics_hash_the_in_place_encoding() {
+        let numbers = Token::Array(vec![Token::Uint(1.into()), Token::Uint(2.into())]);
+        let mut expected = word(Token::Uint(1.into())).to_vec();
+        expected.extend(word(Token::Uint(2.into())));
+        assert_eq!(topic(&numbers), keccak::hash(&expected));
+
+        // Nested strings are padded, without offsets or lengths
+        let labels = Token::Tuple(vec![Token::String("ab".into()), Token::Bool(true)]);
+        let mut expected = [0u8; 64];
+        expected[..2].copy_from_slice(b"ab");
+        expected[63] = 1;
+        assert_eq!(topic(&labels), keccak::hash(&expected));
+    }
+}
@@ -1,15 +1,21 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
 
 use alloc::vec::Vec;
-use ethabi::{decode, encode, ParamType, Token};
+use alloc::string::String;
+use ethabi::{Token, Uint as U256};
+use intran3t_polkavm_contracts::abi::{selector, DispatchError, Uint8Array, MAX_PAGE_BYTES};
+use intran3t_polkavm_contracts::{calls, dispatch, errors, keccak, merkle, personhood, sol_error, sol_event, storage_layout, storage_struct};
+#[cfg(not(feature = "mock"))]
 use polkavm_derive::polkavm_export;
-use uapi::{HostFn, HostFnImpl as api, StorageFlags, ReturnFlags};
+#[cfg(not(feature = "mock"))]
+use uapi::HostFn;
+use intran3t_polkavm_contracts::host::{Api as api, ReturnFlags};
 
 // ============ Runtime support (no_std + alloc) ============
 
+#[cfg(not(feature = "mock"))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     unsafe {
@@ -18,199 +24,542 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
     }
 }
 
+/// Fits the largest calls, see `the_largest_calls_fit_the_heap`
+#[cfg_attr(feature = "mock", allow(dead_code))]
+const HEAP_SIZE: usize = 128 * 1024;
+
+#[cfg(not(feature = "mock"))]
 #[global_allocator]
-static ALLOCATOR: simplealloc::SimpleAlloc<32768> = simplealloc::SimpleAlloc::new();
+static ALLOCATOR: simplealloc::SimpleAlloc<HEAP_SIZE> = simplealloc::SimpleAlloc::new();
+
+// ============ Storage Layout ============
+
+storage_layout! {
+    FORM_TITLE: StorageMap<u64, String> = 0x10;
+    FORM_CREATOR: StorageMap<u64, [u8; 20]> = 0x11;
+    /// Stored lifecycle status, see STATUS_*
+    FORM_STATUS: StorageMap<u64, u8> = 0x12;
+    /// Encryption public key
+    FORM_PUBKEY: StorageMap<u64, Vec<u8>> = 0x13;
+    /// Response IPFS CIDs, indexed by submission_idx
+    RESPONSE_CIDS: StorageVecMap<u64, Vec<u8>> = 0x14;
+    /// (form_id, field_idx, option_idx) → count
+    AGGREGATES: StorageMap<(u64, u8, u8), u64> = 0x16;
+    FORM_COUNT: StorageValue<u64> = 0x17;
+    FORM_DESCRIPTION: StorageMap<u64, String> = 0x19;
+    /// Every fields_json revision; schema version n is entry n - 1
+    FORM_FIELDS: StorageVecMap<u64, Vec<u8>> = 0x1a;
+    FORM_DEADLINE: StorageMap<u64, u64> = 0x1b;
+    FORM_CREATED_AT: StorageMap<u64, u64> = 0x1c;
+    FORM_POLICY: StorageMap<u64, u8> = 0x1d;
+    /// (form_id, voter) → latest submission_idx; present iff the voter submitted
+    VOTER_RESPONSE: StorageMap<(u64, [u8; 20]), u64> = 0x1e;
+    /// Option count per choice field
+    FORM_CHOICES: StorageMap<u64, Vec<u8>> = 0x1f;
+    /// (form_id, voter) → tallied option per choice field
+    VOTER_CHOICES: StorageMap<(u64, [u8; 20]), Vec<u8>> = 0x20;
+    /// (form_id, submission_idx) → schema version the response answered
+    RESPONSE_SCHEMA_VERSION: StorageMap<(u64, u64), u64> = 0x21;
+    /// Earliest response time of a published form; 0 opens immediately
+    FORM_OPENS_AT: StorageMap<u64, u64> = 0x22;
+    /// (form_id, respondent) → true; present iff allowlisted
+    ALLOWLIST: StorageMap<(u64, [u8; 20]), bool> = 0x23;
+    /// Number of allowlisted respondents; present iff the form is restricted
+    ALLOWLIST_SIZE: StorageMap<u64, u64> = 0x24;
+    /// Root of a Merkle tree of eligible addresses; present iff set
+    FORM_MERKLE_ROOT: StorageMap<u64, [u8; 32]> = 0x25;
+    /// AccessPass respondents must hold; present iff the form is pass-gated
+    FORM_PASS_GATE: StorageMap<u64, PassGate> = 0x26;
+    /// Minimum personhood status in the "dotns" context; present iff required
+    FORM_MIN_PERSONHOOD: StorageMap<u64, u8> = 0x27;
+    /// Present iff the form takes pseudonymous responses only
+    FORM_PSEUDONYMOUS: StorageMap<u64, bool> = 0x28;
+    /// (form_id, nullifier) → true; present iff spent
+    SPENT_NULLIFIERS: StorageMap<(u64, [u8; 32]), bool> = 0x29;
+    /// End of the reveal phase; present iff the form uses commit-reveal
+    FORM_REVEAL_DEADLINE: StorageMap<u64, u64> = 0x2a;
+    /// (form_id, submission_idx) → commitment; present iff not yet revealed
+    COMMITMENTS: StorageMap<(u64, u64), [u8; 32]> = 0x2b;
+    UNREVEALED_COUNT: StorageMap<u64, u64> = 0x2c;
+    /// (form_id, submission_idx) → who submitted and when
+    RESPONSE_META: StorageMap<(u64, u64), ResponseMeta> = 0x2d;
+}
+
+storage_struct! {
+    /// AccessPass contract plus the location and level a respondent's pass
+    /// must have; empty strings match any
+    #[derive(Default)]
+    struct PassGate {
+        access_pass: [u8; 20],
+        location_id: String,
+        access_level: String,
+    }
+}
+
+storage_struct! {
+    /// Respondent (zero for pseudonymous responses) and time of the latest
+    /// submission of a response
+    #[derive(Default)]
+    struct ResponseMeta {
+        respondent: [u8; 20],
+        timestamp: u64,
+    }
+}
+
+// ============ Form Status ============
+const STATUS_OPEN: u8 = 0;      // accepting responses
+const STATUS_CLOSED: u8 = 1;    // closed by the creator, can be reopened
+const STATUS_DRAFT: u8 = 2;     // not published yet
+const STATUS_SCHEDULED: u8 = 3; // published, `opensAt` still ahead (derived, never stored)
+const STATUS_ARCHIVED: u8 = 4;  // closed for good, read-only
+const STATUS_DELETED: u8 = 5;   // metadata dropped, the rest reclaimed by `purgeForm`
+
+// ============ Submission Policies ============
+const POLICY_SINGLE: u8 = 0;    // one response per address (default)
+const POLICY_EDITABLE: u8 = 1;  // one response per address, resubmitting replaces it
+const POLICY_UNLIMITED: u8 = 2; // any number of responses per address
+
+const MAX_CHOICE_FIELDS: usize = 255;
+
+// Size limits to prevent memory exhaustion
+const MAX_TITLE_LEN: usize = 256;
+const MAX_DESCRIPTION_LEN: usize = 1024;
+const MAX_FIELDS_LEN: usize = 4096;
+const MAX_CID_LEN: usize = 256;
+const MAX_PUBKEY_LEN: usize = 65; // uncompressed secp256k1
+// Call data is copied to the heap whole. This fits `createForm` with every
+// field at its limit and `MAX_CHOICE_FIELDS` choice fields, or a Merkle
+// proof of about 500 hashes.
+const MAX_CALL_DATA_LEN: usize = 16 * 1024;
+// Respondents per allowlist call; each costs a storage write and an event
+const MAX_ALLOWLIST_BATCH: usize = 200;
+
+// ============ AccessPass ============
+
+const GET_PASS_METADATA: [u8; 4] = selector("getPassMetadata(uint256)");
+const IS_PASS_VALID: [u8; 4] = selector("isPassValid(uint256)");
+
+/// `AccessPassMetadata` returned by `getPassMetadata`: `(location,
+/// locationId, holder, issuedAt, expiresAt, accessLevel, revoked,
+/// identityDisplay)`
+type PassMetadata = (String, String, [u8; 20], U256, U256, String, bool, String);
+
+// ============ Events ============
+
+sol_event! {
+    #[signature("FormCreated(uint64,address,string,uint64,uint64)")]
+    struct FormCreated {
+        #[indexed] form_id: u64,
+        #[indexed] creator: [u8; 20],
+        title: String,
+        deadline: u64,
+        timestamp: u64,
+    }
+}
+
+sol_event! {
+    #[signature("FormClosed(uint64,address,uint64)")]
+    struct FormClosed {
+        #[indexed] form_id: u64,
+        #[indexed] closed_by: [u8; 20],
+        timestamp: u64,
+    }
+}
+
+// Emitted on every lifecycle transition, alongside FormClosed when closing
+sol_event! {
+    #[signature("FormStatusChanged(uint64,address,uint8,uint64)")]
+    struct FormStatusChanged {
+        #[indexed] form_id: u64,
+        #[indexed] changed_by: [u8; 20],
+        status: u8,
+        timestamp: u64,
+    }
+}
+
+// Also emitted for an editable resubmission, with the replaced `responseIdx`
+sol_event! {
+    #[signature("ResponseSubmitted(uint64,address,uint64,bytes,uint64)")]
+    struct ResponseSubmitted {
+        #[indexed] form_id: u64,
+        #[indexed] respondent: [u8; 20],
+        response_idx: u64,
+        cid: Vec<u8>,
+        timestamp: u64,
+    }
+}
 
-// ============ Function Selectors ============
-// Computed from keccak256 of function signatures
+// Replaces ResponseSubmitted and AggregateRecorded for pseudonymous forms
+sol_event! {
+    #[signature("PseudonymousResponseSubmitted(uint64,bytes32,uint64,bytes,uint8[],uint64)")]
+    struct PseudonymousResponseSubmitted {
+        #[indexed] form_id: u64,
+        #[indexed] nullifier: [u8; 32],
+        response_idx: u64,
+        cid: Vec<u8>,
+        choices: Uint8Array,
+        timestamp: u64,
+    }
+}
+
+// Follows the ResponseSubmitted of a commit-reveal response; its
+// AggregateRecorded is emitted on reveal
+sol_event! {
+    #[signature("ResponseCommitted(uint64,address,uint64,bytes32)")]
+    struct ResponseCommitted {
+        #[indexed] form_id: u64,
+        #[indexed] respondent: [u8; 20],
+        response_idx: u64,
+        commitment: [u8; 32],
+    }
+}
 
-const SELECTOR_CREATE_FORM: [u8; 4] = [0xdf, 0x9e, 0xe8, 0xd3];
-// keccak256("createForm(bytes,bytes,bytes,uint64,bytes)")
+sol_event! {
+    #[signature("FormUpdated(uint64,address,uint64)")]
+    struct FormUpdated {
+        #[indexed] form_id: u64,
+        #[indexed] editor: [u8; 20],
+        schema_version: u64,
+    }
+}
 
-const SELECTOR_SUBMIT_RESPONSE: [u8; 4] = [0xae, 0x57, 0xb6, 0x3d];
-// keccak256("submitResponse(uint64,bytes)")
+sol_event! {
+    #[signature("DeadlineExtended(uint64,uint64,uint64)")]
+    struct DeadlineExtended {
+        #[indexed] form_id: u64,
+        old_deadline: u64,
+        new_deadline: u64,
+    }
+}
 
-const SELECTOR_GET_RESPONSE_CID: [u8; 4] = [0xa1, 0xde, 0x25, 0x51];
-// keccak256("getResponseCid(uint64,uint64)")
+sol_event! {
+    #[signature("AllowlistUpdated(uint64,address,bool)")]
+    struct AllowlistUpdated {
+        #[indexed] form_id: u64,
+        #[indexed] respondent: [u8; 20],
+        allowed: bool,
+    }
+}
 
-const SELECTOR_RECORD_AGGREGATE: [u8; 4] = [0x59, 0x40, 0x5b, 0x5f];
-// keccak256("recordAggregate(uint64,uint8,uint8)")
+sol_event! {
+    #[signature("MerkleRootUpdated(uint64,bytes32)")]
+    struct MerkleRootUpdated {
+        #[indexed] form_id: u64,
+        root: [u8; 32],
+    }
+}
 
-const SELECTOR_GET_AGGREGATE: [u8; 4] = [0x59, 0x81, 0x88, 0xae];
-// keccak256("getAggregateCount(uint64,uint8,uint8)")
+sol_event! {
+    #[signature("PassGateUpdated(uint64,address,string,string)")]
+    struct PassGateUpdated {
+        #[indexed] form_id: u64,
+        #[indexed] access_pass: [u8; 20],
+        location_id: String,
+        access_level: String,
+    }
+}
 
-const SELECTOR_HAS_SUBMITTED: [u8; 4] = [0x0e, 0xcf, 0xb4, 0x2b];
-// keccak256("hasSubmitted(uint64,address)")
+sol_event! {
+    #[signature("PersonhoodRequirementUpdated(uint64,uint8)")]
+    struct PersonhoodRequirementUpdated {
+        #[indexed] form_id: u64,
+        min_status: u8,
+    }
+}
 
-const SELECTOR_CLOSE_FORM: [u8; 4] = [0x3d, 0x2b, 0x4a, 0x57];
-// keccak256("closeForm(uint64)")
+sol_event! {
+    #[signature("PseudonymityUpdated(uint64,bool)")]
+    struct PseudonymityUpdated {
+        #[indexed] form_id: u64,
+        pseudonymous: bool,
+    }
+}
 
-const SELECTOR_FORM_COUNT: [u8; 4] = [0x14, 0x6c, 0x14, 0x15];
-// keccak256("formCount()")
+sol_event! {
+    #[signature("CommitRevealUpdated(uint64,uint64)")]
+    struct CommitRevealUpdated {
+        #[indexed] form_id: u64,
+        reveal_deadline: u64,
+    }
+}
 
-const SELECTOR_GET_RESPONSE_COUNT: [u8; 4] = [0x3f, 0x92, 0x51, 0x25];
-// keccak256("getResponseCount(uint64)")
+// The respondent's picks, one per choice field; a later event from the same
+// respondent replaces the earlier tally
+sol_event! {
+    #[signature("AggregateRecorded(uint64,address,uint8[])")]
+    struct AggregateRecorded {
+        #[indexed] form_id: u64,
+        #[indexed] respondent: [u8; 20],
+        choices: Uint8Array,
+    }
+}
 
-// ============ Storage Namespaces ============
-const NS_FORM_META: u8 = 0x10;        // form_id → serialized metadata
-const NS_FORM_CREATOR: u8 = 0x11;    // form_id → creator address (20 bytes)
-const NS_FORM_STATUS: u8 = 0x12;     // form_id → 0=active, 1=closed
-const NS_FORM_PUBKEY: u8 = 0x13;     // form_id → encryption public key (32 bytes)
-const NS_RESPONSE_CID: u8 = 0x14;    // (form_id, submission_idx) → IPFS CID
-const NS_HAS_SUBMITTED: u8 = 0x15;   // (form_id, voter_address) → 1 if submitted
-const NS_AGGREGATE: u8 = 0x16;       // (form_id, field_idx, option_idx) → u64 count
-const NS_FORM_COUNTER: u8 = 0x17;    // global form counter
-const NS_RESPONSE_COUNTER: u8 = 0x18; // form_id → response counter
+// ============ Errors ============
 
-// ============ Storage Key Builders ============
+sol_error! {
+    #[signature("FormNotFound(uint64)")]
+    struct FormNotFound {
+        form_id: u64,
+    }
+}
 
-fn key_form_meta(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_META;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("FormIsClosed(uint64)")]
+    struct FormIsClosed {
+        form_id: u64,
+    }
 }
 
-fn key_form_creator(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_CREATOR;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("FormIsArchived(uint64)")]
+    struct FormIsArchived {
+        form_id: u64,
+    }
 }
 
-fn key_form_status(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_STATUS;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("FormNotOpen(uint64,uint8)")]
+    struct FormNotOpen {
+        form_id: u64,
+        status: u8,
+    }
 }
 
-fn key_form_pubkey(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_PUBKEY;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("FormNotYetOpen(uint64,uint64)")]
+    struct FormNotYetOpen {
+        form_id: u64,
+        opens_at: u64,
+    }
 }
 
-fn key_response_cid(form_id: u64, submission_idx: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_RESPONSE_CID;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k[9..17].copy_from_slice(&submission_idx.to_le_bytes());
-    k
+sol_error! {
+    #[signature("FormNotDeleted(uint64,uint8)")]
+    struct FormNotDeleted {
+        form_id: u64,
+        status: u8,
+    }
 }
 
-fn key_has_submitted(form_id: u64, voter: &[u8; 20]) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_HAS_SUBMITTED;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k[9..29].copy_from_slice(voter);
-    k
+sol_error! {
+    #[signature("InvalidStatusTransition(uint64,uint8,uint8)")]
+    struct InvalidStatusTransition {
+        form_id: u64,
+        status: u8,
+        target: u8,
+    }
 }
 
-fn key_aggregate(form_id: u64, field_idx: u8, option_idx: u8) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_AGGREGATE;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k[9] = field_idx;
-    k[10] = option_idx;
-    k
+sol_error! {
+    #[signature("InvalidOpeningTime(uint64,uint64,uint64)")]
+    struct InvalidOpeningTime {
+        form_id: u64,
+        opens_at: u64,
+        deadline: u64,
+    }
 }
 
-fn key_form_counter() -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_COUNTER;
-    k
+sol_error! {
+    #[signature("DeadlinePassed(uint64,uint64)")]
+    struct DeadlinePassed {
+        form_id: u64,
+        deadline: u64,
+    }
 }
 
-fn key_response_counter(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_RESPONSE_COUNTER;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("NotFormCreator(uint64,address)")]
+    struct NotFormCreator {
+        form_id: u64,
+        caller: [u8; 20],
+    }
 }
 
-fn key_form_title(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_META; // title uses the meta namespace
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("InvalidDeadline(uint64,uint64,uint64)")]
+    struct InvalidDeadline {
+        form_id: u64,
+        deadline: u64,
+        requested: u64,
+    }
 }
 
-fn key_form_description(form_id: u64) -> [u8; 32] {
-    let mut k = [0x8; 32];
-    k[0] = 0x19; // New namespace for description
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("SchemaChangeInvalidatesAggregates(uint64)")]
+    struct SchemaChangeInvalidatesAggregates {
+        form_id: u64,
+    }
 }
 
-fn key_form_fields_json(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = 0x1a; // New namespace for fields_json
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("InputTooLong(string,uint64,uint64)")]
+    struct InputTooLong {
+        field: String,
+        length: u64,
+        max_length: u64,
+    }
 }
 
-fn key_form_deadline(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = 0x1b; // New namespace for deadline
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("InvalidSubmissionPolicy(uint8)")]
+    struct InvalidSubmissionPolicy {
+        policy: u8,
+    }
 }
 
-fn key_form_timestamp(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = 0x1c; // New namespace for timestamp
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+sol_error! {
+    #[signature("TooManyChoiceFields(uint64,uint64)")]
+    struct TooManyChoiceFields {
+        count: u64,
+        max_count: u64,
+    }
 }
 
-// ============ Storage Primitives ============
+sol_error! {
+    #[signature("EmptyChoiceField(uint8)")]
+    struct EmptyChoiceField {
+        field_idx: u8,
+    }
+}
 
-fn get_storage(key: &[u8; 32]) -> Option<Vec<u8>> {
-    let mut buf = [0u8; 256];
-    match api::get_storage(StorageFlags::empty(), key, &mut &mut buf[..]) {
-        Ok(_) => Some(buf.to_vec()),
-        Err(_) => None,
+sol_error! {
+    #[signature("NotEligible(uint64,address)")]
+    struct NotEligible {
+        form_id: u64,
+        respondent: [u8; 20],
     }
 }
 
-fn set_storage(key: &[u8; 32], value: &[u8]) {
-    api::set_storage(StorageFlags::empty(), key, value);
+sol_error! {
+    #[signature("PassRequired(uint64)")]
+    struct PassRequired {
+        form_id: u64,
+    }
 }
 
-fn get_u64(key: &[u8; 32]) -> u64 {
-    match get_storage(key) {
-        Some(b) if b.len() >= 8 => {
-            let mut arr = [0u8; 8];
-            arr.copy_from_slice(&b[..8]);
-            u64::from_le_bytes(arr)
-        }
-        _ => 0,
+sol_error! {
+    #[signature("InvalidPass(uint64,uint256)")]
+    struct InvalidPass {
+        form_id: u64,
+        pass_id: U256,
+    }
+}
+
+sol_error! {
+    #[signature("InvalidPersonhoodStatus(uint8)")]
+    struct InvalidPersonhoodStatus {
+        status: u8,
+    }
+}
+
+sol_error! {
+    #[signature("PersonhoodRequired(uint64,address,uint8,uint8)")]
+    struct PersonhoodRequired {
+        form_id: u64,
+        respondent: [u8; 20],
+        required: u8,
+        status: u8,
+    }
+}
+
+sol_error! {
+    #[signature("WrongSubmissionMode(uint64,bool)")]
+    struct WrongSubmissionMode {
+        form_id: u64,
+        pseudonymous: bool,
+    }
+}
+
+sol_error! {
+    #[signature("WrongCommitRevealMode(uint64,bool)")]
+    struct WrongCommitRevealMode {
+        form_id: u64,
+        commit_reveal: bool,
+    }
+}
+
+sol_error! {
+    #[signature("NotInRevealPhase(uint64,uint64,uint64)")]
+    struct NotInRevealPhase {
+        form_id: u64,
+        deadline: u64,
+        reveal_deadline: u64,
+    }
+}
+
+sol_error! {
+    #[signature("NothingToReveal(uint64,address)")]
+    struct NothingToReveal {
+        form_id: u64,
+        respondent: [u8; 20],
+    }
+}
+
+sol_error! {
+    #[signature("CommitmentMismatch(uint64,address)")]
+    struct CommitmentMismatch {
+        form_id: u64,
+        respondent: [u8; 20],
+    }
+}
+
+sol_error! {
+    #[signature("InvalidNullifier(uint64)")]
+    struct InvalidNullifier {
+        form_id: u64,
+    }
+}
+
+sol_error! {
+    #[signature("PseudonymityRequiresPersonhood(uint64)")]
+    struct PseudonymityRequiresPersonhood {
+        form_id: u64,
+    }
+}
+
+sol_error! {
+    #[signature("NullifierSpent(uint64,bytes32)")]
+    struct NullifierSpent {
+        form_id: u64,
+        nullifier: [u8; 32],
     }
 }
 
-fn set_u64(key: &[u8; 32], v: u64) {
-    set_storage(key, &v.to_le_bytes());
+sol_error! {
+    #[signature("FormHasResponses(uint64)")]
+    struct FormHasResponses {
+        form_id: u64,
+    }
 }
 
-fn get_u8_flag(key: &[u8; 32]) -> u8 {
-    get_storage(key).and_then(|b| b.first().copied()).unwrap_or(0)
+sol_error! {
+    #[signature("AlreadySubmitted(uint64,address)")]
+    struct AlreadySubmitted {
+        form_id: u64,
+        respondent: [u8; 20],
+    }
 }
 
-fn set_u8_flag(key: &[u8; 32], v: u8) {
-    set_storage(key, &[v]);
+sol_error! {
+    #[signature("WrongChoiceCount(uint64,uint64,uint64)")]
+    struct WrongChoiceCount {
+        form_id: u64,
+        expected: u64,
+        given: u64,
+    }
 }
 
-fn get_address(key: &[u8; 32]) -> Option<[u8; 20]> {
-    let b = get_storage(key)?;
-    if b.len() >= 20 {
-        let mut addr = [0u8; 20];
-        addr.copy_from_slice(&b[..20]);
-        Some(addr)
-    } else { None }
+sol_error! {
+    #[signature("InvalidChoice(uint64,uint8,uint8)")]
+    struct InvalidChoice {
+        form_id: u64,
+        field_idx: u8,
+        option_idx: u8,
+    }
 }
 
+// ============ Environment ============
+
 fn get_caller() -> [u8; 20] {
     let mut caller = [0u8; 20];
     api::caller(&mut caller);
@@ -228,264 +577,2426 @@ fn get_timestamp() -> u64 {
 // ============ Contract Logic ============
 
 fn create_form(
-    title: Vec<u8>,
-    description: Vec<u8>,
+    title: String,
+    description: String,
+    fields_json: Vec<u8>,
+    deadline: u64,
+    encryption_pub_key: Vec<u8>,
+) -> u64 {
+    create_form_with_choices(title, description, fields_json, deadline, encryption_pub_key, POLICY_SINGLE, Uint8Array::default())
+}
+
+fn create_form_with_policy(
+    title: String,
+    description: String,
+    fields_json: Vec<u8>,
+    deadline: u64,
+    encryption_pub_key: Vec<u8>,
+    policy: u8,
+) -> u64 {
+    create_form_with_choices(title, description, fields_json, deadline, encryption_pub_key, policy, Uint8Array::default())
+}
+
+/// `option_counts[i]` is the number of options of the i-th choice field;
+/// every response must then pick exactly one option per choice field.
+fn create_form_with_choices(
+    title: String,
+    description: String,
+    fields_json: Vec<u8>,
+    deadline: u64,
+    encryption_pub_key: Vec<u8>,
+    policy: u8,
+    option_counts: Uint8Array,
+) -> u64 {
+    new_form(title, description, fields_json, deadline, encryption_pub_key, policy, option_counts, STATUS_OPEN)
+}
+
+/// Create a form that only `allowlist` may respond to; with an empty list
+/// anyone may
+#[allow(clippy::too_many_arguments)]
+fn create_form_with_allowlist(
+    title: String,
+    description: String,
+    fields_json: Vec<u8>,
+    deadline: u64,
+    encryption_pub_key: Vec<u8>,
+    policy: u8,
+    option_counts: Uint8Array,
+    allowlist: Vec<[u8; 20]>,
+) -> u64 {
+    let form_id = create_form_with_choices(title, description, fields_json, deadline, encryption_pub_key, policy, option_counts);
+    allow_respondents(form_id, allowlist);
+    form_id
+}
+
+/// Like `createForm`, but the form takes no responses until `publishForm`
+fn create_draft(
+    title: String,
+    description: String,
+    fields_json: Vec<u8>,
+    deadline: u64,
+    encryption_pub_key: Vec<u8>,
+    policy: u8,
+    option_counts: Uint8Array,
+) -> u64 {
+    new_form(title, description, fields_json, deadline, encryption_pub_key, policy, option_counts, STATUS_DRAFT)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn new_form(
+    title: String,
+    description: String,
     fields_json: Vec<u8>,
     deadline: u64,
     encryption_pub_key: Vec<u8>,
+    policy: u8,
+    option_counts: Uint8Array,
+    status: u8,
 ) -> u64 {
-    // Size limits to prevent memory exhaustion
-    if title.len() > 256 || description.len() > 1024 || fields_json.len() > 4096 {
-        return 0; // Return 0 on invalid input
+    require_valid_content(&title, &description, &fields_json);
+    if policy > POLICY_UNLIMITED {
+        InvalidSubmissionPolicy { policy }.revert();
     }
+    let option_counts = option_counts.0;
+    require_valid_option_counts(&option_counts);
+    require_max_len("encryptionPubKey", encryption_pub_key.len(), MAX_PUBKEY_LEN);
 
     let creator = get_caller();
-    let counter_key = key_form_counter();
-    let form_id = get_u64(&counter_key) + 1;
-    set_u64(&counter_key, form_id);
-
-    set_storage(&key_form_creator(form_id), &creator);
-    set_u8_flag(&key_form_status(form_id), 0);
+    let form_id = FORM_COUNT.get_or_default() + 1;
+    FORM_COUNT.set(&form_id);
+
+    FORM_CREATOR.set(&form_id, &creator);
+    FORM_STATUS.set(&form_id, &status);
+    FORM_POLICY.set(&form_id, &policy);
+    if !option_counts.is_empty() {
+        FORM_CHOICES.set(&form_id, &option_counts);
+    }
 
-    let pk_len = encryption_pub_key.len().min(65);
-    set_storage(&key_form_pubkey(form_id), &encryption_pub_key[..pk_len]);
+    FORM_PUBKEY.set(&form_id, &encryption_pub_key);
 
     // Store fields separately to avoid ethabi encoding overhead (memory limits)
-    set_storage(&key_form_title(form_id), &title);
-    set_storage(&key_form_description(form_id), &description);
-    set_storage(&key_form_fields_json(form_id), &fields_json);
-
-    let timestamp = get_timestamp();
-    let mut deadline_bytes = [0u8; 8];
-    deadline_bytes.copy_from_slice(&deadline.to_le_bytes());
-    set_storage(&key_form_deadline(form_id), &deadline_bytes);
+    FORM_TITLE.set(&form_id, &title);
+    FORM_DESCRIPTION.set(&form_id, &description);
+    FORM_FIELDS.at(&form_id).push(&fields_json);
+    FORM_DEADLINE.set(&form_id, &deadline);
+    let now = get_timestamp();
+    FORM_CREATED_AT.set(&form_id, &now);
 
-    let mut ts_bytes = [0u8; 8];
-    ts_bytes.copy_from_slice(&timestamp.to_le_bytes());
-    set_storage(&key_form_timestamp(form_id), &ts_bytes);
+    FormCreated { form_id, creator, title, deadline, timestamp: now }.emit();
 
     form_id
 }
 
-fn submit_response(form_id: u64, cid: Vec<u8>) -> u64 {
-    let voter = get_caller(); // Derived from Alice's relay Substrate address via pallet-revive
-    let status = get_u8_flag(&key_form_status(form_id));
-    if status != 0 { return u64::MAX; }
-    if get_address(&key_form_creator(form_id)).is_none() { return u64::MAX; }
-
-    // Check deadline (stored separately after memory fix)
-    let deadline_bytes = get_storage(&key_form_deadline(form_id)).unwrap_or_default();
-    if deadline_bytes.len() >= 8 {
-        let mut deadline_arr = [0u8; 8];
-        deadline_arr.copy_from_slice(&deadline_bytes[..8]);
-        let deadline = u64::from_le_bytes(deadline_arr);
-        if deadline > 0 && get_timestamp() > deadline {
-            return u64::MAX;
-        }
+fn require_max_len(field: &str, length: usize, max_length: usize) {
+    if length > max_length {
+        InputTooLong { field: field.into(), length: length as u64, max_length: max_length as u64 }.revert();
     }
+}
 
-    set_u8_flag(&key_has_submitted(form_id, &voter), 1);
+fn require_valid_content(title: &str, description: &str, fields_json: &[u8]) {
+    require_max_len("title", title.len(), MAX_TITLE_LEN);
+    require_max_len("description", description.len(), MAX_DESCRIPTION_LEN);
+    require_max_len("fieldsJson", fields_json.len(), MAX_FIELDS_LEN);
+}
 
-    let counter_key = key_response_counter(form_id);
-    let idx = get_u64(&counter_key);
-    set_u64(&counter_key, idx + 1);
+fn require_valid_option_counts(option_counts: &[u8]) {
+    if option_counts.len() > MAX_CHOICE_FIELDS {
+        TooManyChoiceFields { count: option_counts.len() as u64, max_count: MAX_CHOICE_FIELDS as u64 }.revert();
+    }
+    if let Some(field_idx) = option_counts.iter().position(|count| *count == 0) {
+        EmptyChoiceField { field_idx: field_idx as u8 }.revert();
+    }
+}
 
-    let cid_key = key_response_cid(form_id, idx);
-    let cid_len = cid.len().min(256);
-    set_storage(&cid_key, &cid[..cid_len]);
+/// Creator of an existing form; reverts for anyone else
+fn require_creator(form_id: u64) -> [u8; 20] {
+    let caller = get_caller();
+    if require_form(form_id) != caller {
+        NotFormCreator { form_id, caller }.revert();
+    }
+    caller
+}
 
-    idx
+/// Edit title, description and fields, keeping the current choice fields
+fn update_form(form_id: u64, title: String, description: String, fields_json: Vec<u8>) -> u64 {
+    let option_counts = FORM_CHOICES.get_or_default(&form_id);
+    update_form_with_choices(form_id, title, description, fields_json, Uint8Array(option_counts))
 }
 
-fn get_response_cid(form_id: u64, submission_idx: u64) -> Vec<u8> {
-    get_storage(&key_response_cid(form_id, submission_idx)).unwrap_or_default()
+/// Edit a form; changed `fields_json` becomes a new schema version, which is
+/// returned. Once responses exist, choice fields can only gain options, so
+/// every recorded aggregate keeps its meaning.
+fn update_form_with_choices(
+    form_id: u64,
+    title: String,
+    description: String,
+    fields_json: Vec<u8>,
+    option_counts: Uint8Array,
+) -> u64 {
+    let editor = require_creator(form_id);
+    require_not_archived(form_id);
+    require_valid_content(&title, &description, &fields_json);
+    let option_counts = option_counts.0;
+    require_valid_option_counts(&option_counts);
+
+    let current_counts = FORM_CHOICES.get_or_default(&form_id);
+    if option_counts != current_counts && !RESPONSE_CIDS.at(&form_id).is_empty() {
+        let keeps_options = option_counts.len() == current_counts.len()
+            && option_counts.iter().zip(&current_counts).all(|(new, old)| new >= old);
+        if !keeps_options {
+            SchemaChangeInvalidatesAggregates { form_id }.revert();
+        }
+    }
+
+    let versions = FORM_FIELDS.at(&form_id);
+    if versions.get(versions.len() - 1).as_ref() != Some(&fields_json) {
+        versions.push(&fields_json);
+    }
+    FORM_TITLE.set(&form_id, &title);
+    FORM_DESCRIPTION.set(&form_id, &description);
+    if option_counts.is_empty() {
+        FORM_CHOICES.remove(&form_id);
+    } else {
+        FORM_CHOICES.set(&form_id, &option_counts);
+    }
+
+    let schema_version = versions.len();
+    FormUpdated { form_id, editor, schema_version }.emit();
+
+    schema_version
 }
 
-fn record_aggregate(form_id: u64, field_idx: u8, option_idx: u8) {
-    let key = key_aggregate(form_id, field_idx, option_idx);
-    let current = get_u64(&key);
-    set_u64(&key, current + 1);
+/// Push back the deadline of a form that is not archived; 0 removes the
+/// deadline
+fn extend_deadline(form_id: u64, new_deadline: u64) -> bool {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    if deadline == 0 || (new_deadline != 0 && new_deadline <= deadline) {
+        InvalidDeadline { form_id, deadline, requested: new_deadline }.revert();
+    }
+    if let Some(reveal_deadline) = FORM_REVEAL_DEADLINE.get(&form_id) {
+        // Reveals may already be tallied once the commit phase is over
+        if get_timestamp() > deadline {
+            DeadlinePassed { form_id, deadline }.revert();
+        }
+        if new_deadline == 0 || new_deadline >= reveal_deadline {
+            InvalidDeadline { form_id, deadline, requested: new_deadline }.revert();
+        }
+    }
+
+    FORM_DEADLINE.set(&form_id, &new_deadline);
+    DeadlineExtended { form_id, old_deadline: deadline, new_deadline }.emit();
+    true
 }
 
-fn get_aggregate_count(form_id: u64, field_idx: u8, option_idx: u8) -> u64 {
-    get_u64(&key_aggregate(form_id, field_idx, option_idx))
+/// Restrict the form to allowlisted respondents and add `respondents`, at
+/// most `MAX_ALLOWLIST_BATCH` per call; returns how many were newly added.
+/// An empty list leaves the form as it is.
+fn add_to_allowlist(form_id: u64, respondents: Vec<[u8; 20]>) -> u64 {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    allow_respondents(form_id, respondents)
 }
 
-fn has_submitted(form_id: u64, voter: [u8; 20]) -> bool {
-    get_u8_flag(&key_has_submitted(form_id, &voter)) == 1
+fn allow_respondents(form_id: u64, respondents: Vec<[u8; 20]>) -> u64 {
+    require_max_len("respondents", respondents.len(), MAX_ALLOWLIST_BATCH);
+    if respondents.is_empty() {
+        return 0;
+    }
+    let mut added = 0;
+    for respondent in respondents {
+        if !ALLOWLIST.contains(&(form_id, respondent)) {
+            ALLOWLIST.set(&(form_id, respondent), &true);
+            AllowlistUpdated { form_id, respondent, allowed: true }.emit();
+            added += 1;
+        }
+    }
+    ALLOWLIST_SIZE.set(&form_id, &(ALLOWLIST_SIZE.get_or_default(&form_id) + added));
+    added
 }
 
-fn close_form(form_id: u64) {
-    let caller = get_caller();
-    if let Some(creator) = get_address(&key_form_creator(form_id)) {
-        if creator == caller {
-            set_u8_flag(&key_form_status(form_id), 1);
+/// Remove `respondents`, returning how many were listed; the form stays
+/// restricted even if none are left
+fn remove_from_allowlist(form_id: u64, respondents: Vec<[u8; 20]>) -> u64 {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    require_max_len("respondents", respondents.len(), MAX_ALLOWLIST_BATCH);
+    let mut removed = 0;
+    for respondent in respondents {
+        if ALLOWLIST.contains(&(form_id, respondent)) {
+            ALLOWLIST.remove(&(form_id, respondent));
+            AllowlistUpdated { form_id, respondent, allowed: false }.emit();
+            removed += 1;
         }
     }
+    if let Some(size) = ALLOWLIST_SIZE.get(&form_id) {
+        ALLOWLIST_SIZE.set(&form_id, &size.saturating_sub(removed));
+    }
+    removed
 }
 
-fn get_response_count(form_id: u64) -> u64 {
-    get_u64(&key_response_counter(form_id))
+/// Let every address in the Merkle tree with `root` respond, in addition to
+/// the allowlist; a zero root removes the tree. Leaves are built as by
+/// `StandardMerkleTree.of(addresses, ["address"])`.
+fn set_merkle_root(form_id: u64, root: [u8; 32]) -> bool {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    if root == [0u8; 32] {
+        FORM_MERKLE_ROOT.remove(&form_id);
+    } else {
+        FORM_MERKLE_ROOT.set(&form_id, &root);
+    }
+    MerkleRootUpdated { form_id, root }.emit();
+    true
 }
 
-fn form_count() -> u64 {
-    get_u64(&key_form_counter())
+fn get_merkle_root(form_id: u64) -> [u8; 32] {
+    require_form(form_id);
+    FORM_MERKLE_ROOT.get_or_default(&form_id)
 }
 
-// ============ Dispatcher ============
+/// Whether `respondent` passes the form's eligibility checks. Restricted
+/// forms accept allowlisted addresses and, with `proof`, members of the
+/// Merkle tree; without a proof only the allowlist is checked. Pass-gated
+/// forms need the overload taking a pass ID.
+fn is_eligible(form_id: u64, respondent: [u8; 20]) -> bool {
+    is_eligible_with_proof(form_id, respondent, Vec::new())
+}
 
-#[no_mangle]
-#[polkavm_export]
-pub extern "C" fn call() {
-    let length = api::call_data_size() as usize;
-    if length < 4 {
-        api::return_value(ReturnFlags::REVERT, b"Input too short");
-        return;
+fn is_eligible_with_proof(form_id: u64, respondent: [u8; 20], proof: Vec<[u8; 32]>) -> bool {
+    require_form(form_id);
+    eligible(form_id, respondent, &proof)
+        && !FORM_PASS_GATE.contains(&form_id)
+        && has_personhood(form_id, respondent)
+}
+
+/// Eligibility of a respondent presenting `pass_id` to a pass-gated form;
+/// queries the AccessPass contract
+fn is_eligible_with_pass(form_id: u64, respondent: [u8; 20], proof: Vec<[u8; 32]>, pass_id: U256) -> bool {
+    require_form(form_id);
+    eligible(form_id, respondent, &proof)
+        && FORM_PASS_GATE.get(&form_id).is_none_or(|gate| holds_pass(&gate, respondent, pass_id))
+        && has_personhood(form_id, respondent)
+}
+
+/// Only accept respondents with at least `min_status` personhood (1 = lite,
+/// 2 = full) in the "dotns" context; 0 removes the requirement, except from
+/// pseudonymous forms
+fn set_personhood_requirement(form_id: u64, min_status: u8) -> bool {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    match min_status {
+        personhood::STATUS_NONE if FORM_PSEUDONYMOUS.contains(&form_id) => PseudonymityRequiresPersonhood { form_id }.revert(),
+        personhood::STATUS_NONE => FORM_MIN_PERSONHOOD.remove(&form_id),
+        personhood::STATUS_LITE | personhood::STATUS_FULL => FORM_MIN_PERSONHOOD.set(&form_id, &min_status),
+        status => InvalidPersonhoodStatus { status }.revert(),
     }
+    PersonhoodRequirementUpdated { form_id, min_status }.emit();
+    true
+}
 
-    let mut selector = [0u8; 4];
-    api::call_data_copy(&mut selector, 0);
+fn get_personhood_requirement(form_id: u64) -> u8 {
+    require_form(form_id);
+    FORM_MIN_PERSONHOOD.get_or_default(&form_id)
+}
 
-    let data_len = length.saturating_sub(4).min(4096);
-    let mut data = Vec::new();
-    if data_len > 0 {
-        let mut buf = [0u8; 4096];
-        api::call_data_copy(&mut buf[..data_len], 4);
-        data = buf[..data_len].to_vec();
-    }
-
-    match selector {
-        SELECTOR_CREATE_FORM => {
-            let decoded = match decode(
-                &[ParamType::String, ParamType::String, ParamType::Bytes, ParamType::Uint(64), ParamType::Bytes],
-                &data
-            ) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+fn has_personhood(form_id: u64, respondent: [u8; 20]) -> bool {
+    FORM_MIN_PERSONHOOD
+        .get(&form_id)
+        .is_none_or(|required| personhood::status(&respondent, &personhood::DOTNS_CONTEXT) >= required)
+}
 
-            let title = match &decoded[0] { Token::String(s) => s.as_bytes().to_vec(), _ => Vec::new() };
-            let desc = match &decoded[1] { Token::String(s) => s.as_bytes().to_vec(), _ => Vec::new() };
-            let fields = match &decoded[2] { Token::Bytes(b) => b.clone(), _ => Vec::new() };
-            let deadline = match &decoded[3] { Token::Uint(u) => u.as_u64(), _ => 0 };
-            let pub_key = match &decoded[4] { Token::Bytes(b) => b.clone(), _ => Vec::new() };
+/// Restrict the form to holders of a valid pass from the `access_pass`
+/// contract, optionally for one location and access level; the zero address
+/// removes the gate
+fn set_pass_gate(form_id: u64, access_pass: [u8; 20], location_id: String, access_level: String) -> bool {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    require_max_len("locationId", location_id.len(), MAX_TITLE_LEN);
+    require_max_len("accessLevel", access_level.len(), MAX_TITLE_LEN);
+    if access_pass == [0u8; 20] {
+        FORM_PASS_GATE.remove(&form_id);
+    } else {
+        let gate = PassGate { access_pass, location_id: location_id.clone(), access_level: access_level.clone() };
+        FORM_PASS_GATE.set(&form_id, &gate);
+    }
+    PassGateUpdated { form_id, access_pass, location_id, access_level }.emit();
+    true
+}
 
-            let form_id = create_form(title, desc, fields, deadline, pub_key);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Uint(form_id.into())]));
-        }
+/// `(accessPass, locationId, accessLevel)`; a zero address if not gated
+fn get_pass_gate(form_id: u64) -> ([u8; 20], String, String) {
+    require_form(form_id);
+    let gate = FORM_PASS_GATE.get(&form_id).unwrap_or_default();
+    (gate.access_pass, gate.location_id, gate.access_level)
+}
 
-        SELECTOR_SUBMIT_RESPONSE => {
-            let decoded = match decode(
-                &[ParamType::Uint(64), ParamType::Bytes],
-                &data
-            ) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+/// Whether `respondent` holds pass `pass_id` for the gate's location and
+/// level, and AccessPass reports it valid (not revoked or expired). A
+/// failing or malformed call counts as no pass.
+fn holds_pass(gate: &PassGate, respondent: [u8; 20], pass_id: U256) -> bool {
+    let args = [Token::Uint(pass_id)];
+    let Ok((_, location_id, holder, _, _, access_level, _, _)) =
+        calls::call_view::<PassMetadata>(&gate.access_pass, GET_PASS_METADATA, &args)
+    else {
+        return false;
+    };
+    holder == respondent
+        && (gate.location_id.is_empty() || location_id == gate.location_id)
+        && (gate.access_level.is_empty() || access_level == gate.access_level)
+        && calls::call_view::<bool>(&gate.access_pass, IS_PASS_VALID, &args) == Ok(true)
+}
+
+fn eligible(form_id: u64, respondent: [u8; 20], proof: &[[u8; 32]]) -> bool {
+    let root = FORM_MERKLE_ROOT.get(&form_id);
+    if root.is_none() && !ALLOWLIST_SIZE.contains(&form_id) {
+        return true;
+    }
+    ALLOWLIST.contains(&(form_id, respondent))
+        || root.is_some_and(|root| merkle::verify(proof, &root, merkle::address_leaf(&respondent)))
+}
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => 0 };
-            let cid = match &decoded[1] { Token::Bytes(b) => b.clone(), _ => Vec::new() };
+fn has_allowlist(form_id: u64) -> bool {
+    require_form(form_id);
+    ALLOWLIST_SIZE.contains(&form_id)
+}
 
-            let idx = submit_response(form_id, cid);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Uint(idx.into())]));
-        }
+fn get_allowlist_size(form_id: u64) -> u64 {
+    require_form(form_id);
+    ALLOWLIST_SIZE.get_or_default(&form_id)
+}
 
-        SELECTOR_GET_RESPONSE_CID => {
-            let decoded = match decode(&[ParamType::Uint(64), ParamType::Uint(64)], &data) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+fn submit_response(form_id: u64, cid: Vec<u8>) -> u64 {
+    submit_response_with_choices(form_id, cid, Uint8Array::default())
+}
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => 0 };
-            let idx = match &decoded[1] { Token::Uint(u) => u.as_u64(), _ => 0 };
+/// Submit a response and tally its choices in one call; `choices[i]` is the
+/// option picked for the i-th choice field declared at `createForm`.
+fn submit_response_with_choices(form_id: u64, cid: Vec<u8>, choices: Uint8Array) -> u64 {
+    submit_response_with_proof(form_id, cid, choices, Vec::new())
+}
+
+/// Submit to a Merkle-gated form; `proof` shows the caller is in the tree
+fn submit_response_with_proof(form_id: u64, cid: Vec<u8>, choices: Uint8Array, proof: Vec<[u8; 32]>) -> u64 {
+    submit(form_id, cid, choices, &proof, None)
+}
+
+/// Submit to a pass-gated form, presenting the caller's pass `pass_id`;
+/// `proof` may be empty unless the form also has a Merkle root
+fn submit_response_with_pass(form_id: u64, cid: Vec<u8>, choices: Uint8Array, proof: Vec<[u8; 32]>, pass_id: U256) -> u64 {
+    submit(form_id, cid, choices, &proof, Some(pass_id))
+}
 
-            let cid = get_response_cid(form_id, idx);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Bytes(cid)]));
+fn submit(form_id: u64, cid: Vec<u8>, choices: Uint8Array, proof: &[[u8; 32]], pass_id: Option<U256>) -> u64 {
+    let voter = get_caller();
+    let (now, _) = require_accepting(form_id, voter, proof, pass_id);
+    if FORM_PSEUDONYMOUS.contains(&form_id) {
+        WrongSubmissionMode { form_id, pseudonymous: true }.revert();
+    }
+    if FORM_REVEAL_DEADLINE.contains(&form_id) {
+        WrongCommitRevealMode { form_id, commit_reveal: true }.revert();
+    }
+    let choices = choices.0;
+    require_valid_choices(form_id, &choices);
+
+    require_max_len("cid", cid.len(), MAX_CID_LEN);
+    let responses = RESPONSE_CIDS.at(&form_id);
+    let schema_version = FORM_FIELDS.at(&form_id).len();
+
+    if let Some(idx) = VOTER_RESPONSE.get(&(form_id, voter)) {
+        match FORM_POLICY.get_or_default(&form_id) {
+            POLICY_UNLIMITED => {}
+            POLICY_EDITABLE => {
+                // Replace the earlier response and its tallies in place; the count is unchanged
+                responses.set(idx, &cid);
+                RESPONSE_SCHEMA_VERSION.set(&(form_id, idx), &schema_version);
+                RESPONSE_META.set(&(form_id, idx), &ResponseMeta { respondent: voter, timestamp: now });
+                untally(form_id, &VOTER_CHOICES.get_or_default(&(form_id, voter)));
+                tally(form_id, &choices);
+                VOTER_CHOICES.set(&(form_id, voter), &choices);
+                emit_submission(form_id, voter, idx, cid, choices, now);
+                return idx;
+            }
+            _ => AlreadySubmitted { form_id, respondent: voter }.revert(),
         }
+    }
 
-        SELECTOR_RECORD_AGGREGATE => {
-            let decoded = match decode(
-                &[ParamType::Uint(64), ParamType::Uint(8), ParamType::Uint(8)],
-                &data
-            ) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+    tally(form_id, &choices);
+    if !choices.is_empty() {
+        VOTER_CHOICES.set(&(form_id, voter), &choices);
+    }
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => { api::return_value(ReturnFlags::REVERT, b"Bad args"); return; } };
-            let field_idx = match &decoded[1] { Token::Uint(u) => u.as_u32() as u8, _ => { api::return_value(ReturnFlags::REVERT, b"Bad args"); return; } };
-            let option_idx = match &decoded[2] { Token::Uint(u) => u.as_u32() as u8, _ => { api::return_value(ReturnFlags::REVERT, b"Bad args"); return; } };
+    let idx = responses.push(&cid);
+    RESPONSE_SCHEMA_VERSION.set(&(form_id, idx), &schema_version);
+    RESPONSE_META.set(&(form_id, idx), &ResponseMeta { respondent: voter, timestamp: now });
+    VOTER_RESPONSE.set(&(form_id, voter), &idx);
+    emit_submission(form_id, voter, idx, cid, choices, now);
 
-            record_aggregate(form_id, field_idx, option_idx);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Bool(true)]));
-        }
+    idx
+}
 
-        SELECTOR_GET_AGGREGATE => {
-            let decoded = match decode(
-                &[ParamType::Uint(64), ParamType::Uint(8), ParamType::Uint(8)],
-                &data
-            ) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+/// Submit to a pseudonymous form. `nullifier` must be the caller's
+/// `computeNullifier(contextAlias, formId)`, so each person responds once
+/// whatever the submission policy. Storage and events keep the nullifier,
+/// not the caller, but this is a pseudonym and not anonymity: the caller is
+/// the transaction sender, and anyone can derive a person's nullifier from
+/// their public context alias.
+fn submit_pseudonymous_response(form_id: u64, nullifier: [u8; 32], cid: Vec<u8>, choices: Uint8Array) -> u64 {
+    submit_pseudonymous(form_id, nullifier, cid, choices, &[], None)
+}
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => 0 };
-            let field_idx = match &decoded[1] { Token::Uint(u) => u.as_u32() as u8, _ => 0 };
-            let option_idx = match &decoded[2] { Token::Uint(u) => u.as_u32() as u8, _ => 0 };
+/// Pseudonymous submission to a gated form; `pass_id` only matters if the form
+/// is pass-gated
+fn submit_pseudonymous_response_with_pass(
+    form_id: u64,
+    nullifier: [u8; 32],
+    cid: Vec<u8>,
+    choices: Uint8Array,
+    proof: Vec<[u8; 32]>,
+    pass_id: U256,
+) -> u64 {
+    submit_pseudonymous(form_id, nullifier, cid, choices, &proof, Some(pass_id))
+}
 
-            let count = get_aggregate_count(form_id, field_idx, option_idx);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Uint(count.into())]));
-        }
+fn submit_pseudonymous(
+    form_id: u64,
+    nullifier: [u8; 32],
+    cid: Vec<u8>,
+    choices: Uint8Array,
+    proof: &[[u8; 32]],
+    pass_id: Option<U256>,
+) -> u64 {
+    let (now, context_alias) = require_accepting(form_id, get_caller(), proof, pass_id);
+    if !FORM_PSEUDONYMOUS.contains(&form_id) {
+        WrongSubmissionMode { form_id, pseudonymous: false }.revert();
+    }
+    // Pseudonymous forms always require personhood, so the alias is the caller's
+    if nullifier != nullifier_of(&context_alias, form_id) {
+        InvalidNullifier { form_id }.revert();
+    }
+    if SPENT_NULLIFIERS.contains(&(form_id, nullifier)) {
+        NullifierSpent { form_id, nullifier }.revert();
+    }
+    let choices = choices.0;
+    require_valid_choices(form_id, &choices);
 
-        SELECTOR_HAS_SUBMITTED => {
-            let decoded = match decode(&[ParamType::Uint(64), ParamType::Address], &data) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+    require_max_len("cid", cid.len(), MAX_CID_LEN);
+    tally(form_id, &choices);
+    SPENT_NULLIFIERS.set(&(form_id, nullifier), &true);
+    let response_idx = RESPONSE_CIDS.at(&form_id).push(&cid);
+    RESPONSE_SCHEMA_VERSION.set(&(form_id, response_idx), &FORM_FIELDS.at(&form_id).len());
+    RESPONSE_META.set(&(form_id, response_idx), &ResponseMeta { respondent: [0u8; 20], timestamp: now });
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => 0 };
-            let mut voter = [0u8; 20];
-            if let Token::Address(addr) = &decoded[1] { voter.copy_from_slice(&addr.0); }
+    PseudonymousResponseSubmitted { form_id, nullifier, response_idx, cid, choices: Uint8Array(choices), timestamp: now }.emit();
 
-            let result = has_submitted(form_id, voter);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Bool(result)]));
-        }
+    response_idx
+}
 
-        SELECTOR_CLOSE_FORM => {
-            let decoded = match decode(&[ParamType::Uint(64)], &data) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+/// Commit to choices on a commit-reveal form without disclosing them; see
+/// `computeCommitment`. An editable form accepts a new commitment that
+/// replaces the old one until the deadline.
+fn commit_response(form_id: u64, commitment: [u8; 32], cid: Vec<u8>) -> u64 {
+    commit(form_id, commitment, cid, &[], None)
+}
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => { api::return_value(ReturnFlags::REVERT, b"Bad args"); return; } };
-            close_form(form_id);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Bool(true)]));
-        }
+/// Commit to a gated form; `pass_id` only matters if the form is pass-gated
+fn commit_response_with_pass(form_id: u64, commitment: [u8; 32], cid: Vec<u8>, proof: Vec<[u8; 32]>, pass_id: U256) -> u64 {
+    commit(form_id, commitment, cid, &proof, Some(pass_id))
+}
 
-        SELECTOR_FORM_COUNT => {
-            let count = form_count();
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Uint(count.into())]));
-        }
+fn commit(form_id: u64, commitment: [u8; 32], cid: Vec<u8>, proof: &[[u8; 32]], pass_id: Option<U256>) -> u64 {
+    let voter = get_caller();
+    let (now, _) = require_accepting(form_id, voter, proof, pass_id);
+    if !FORM_REVEAL_DEADLINE.contains(&form_id) {
+        WrongCommitRevealMode { form_id, commit_reveal: false }.revert();
+    }
 
-        SELECTOR_GET_RESPONSE_COUNT => {
-            let decoded = match decode(&[ParamType::Uint(64)], &data) {
-                Ok(d) => d,
-                Err(_) => { api::return_value(ReturnFlags::REVERT, b"Decode error"); return; }
-            };
+    require_max_len("cid", cid.len(), MAX_CID_LEN);
+    let responses = RESPONSE_CIDS.at(&form_id);
+    let schema_version = FORM_FIELDS.at(&form_id).len();
 
-            let form_id = match &decoded[0] { Token::Uint(u) => u.as_u64(), _ => 0 };
-            let count = get_response_count(form_id);
-            api::return_value(ReturnFlags::empty(), &encode(&[Token::Uint(count.into())]));
+    let idx = match VOTER_RESPONSE.get(&(form_id, voter)) {
+        // Still before the deadline, so the earlier commitment is unrevealed
+        Some(idx) if FORM_POLICY.get_or_default(&form_id) == POLICY_EDITABLE => {
+            responses.set(idx, &cid);
+            idx
         }
-
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"Unknown selector");
+        Some(_) => AlreadySubmitted { form_id, respondent: voter }.revert(),
+        None => {
+            let idx = responses.push(&cid);
+            VOTER_RESPONSE.set(&(form_id, voter), &idx);
+            UNREVEALED_COUNT.set(&form_id, &(UNREVEALED_COUNT.get_or_default(&form_id) + 1));
+            idx
         }
+    };
+    RESPONSE_SCHEMA_VERSION.set(&(form_id, idx), &schema_version);
+    RESPONSE_META.set(&(form_id, idx), &ResponseMeta { respondent: voter, timestamp: now });
+    COMMITMENTS.set(&(form_id, idx), &commitment);
+
+    emit_submission(form_id, voter, idx, cid, Vec::new(), now);
+    ResponseCommitted { form_id, respondent: voter, response_idx: idx, commitment }.emit();
+
+    idx
+}
+
+/// Reveal the caller's committed choices between the deadline and the
+/// reveal deadline, tallying them; also allowed once the form is closed
+fn reveal_response(form_id: u64, choices: Uint8Array, salt: [u8; 32]) -> bool {
+    let voter = get_caller();
+    require_form(form_id);
+    let Some(reveal_deadline) = FORM_REVEAL_DEADLINE.get(&form_id) else {
+        WrongCommitRevealMode { form_id, commit_reveal: false }.revert()
+    };
+    match form_status(form_id) {
+        STATUS_OPEN | STATUS_CLOSED => {}
+        status => FormNotOpen { form_id, status }.revert(),
+    }
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    let now = get_timestamp();
+    if now <= deadline || now > reveal_deadline {
+        NotInRevealPhase { form_id, deadline, reveal_deadline }.revert();
+    }
+
+    let unrevealed = VOTER_RESPONSE
+        .get(&(form_id, voter))
+        .and_then(|idx| COMMITMENTS.get(&(form_id, idx)).map(|commitment| (idx, commitment)));
+    let Some((idx, commitment)) = unrevealed else {
+        NothingToReveal { form_id, respondent: voter }.revert()
+    };
+    let choices = choices.0;
+    if commitment_of(&choices, &salt) != commitment {
+        CommitmentMismatch { form_id, respondent: voter }.revert();
     }
+    require_valid_choices(form_id, &choices);
+
+    tally(form_id, &choices);
+    VOTER_CHOICES.set(&(form_id, voter), &choices);
+    COMMITMENTS.remove(&(form_id, idx));
+    UNREVEALED_COUNT.set(&form_id, &UNREVEALED_COUNT.get_or_default(&form_id).saturating_sub(1));
+    AggregateRecorded { form_id, respondent: voter, choices: Uint8Array(choices) }.emit();
+    true
 }
 
-#[no_mangle]
-#[polkavm_export]
-pub extern "C" fn deploy() {
-    // No constructor logic needed
+/// `keccak256(contextAlias ++ formId)`, the form ID as 8 big-endian bytes:
+/// the nullifier a person with `context_alias` in the "dotns" context submits
+/// pseudonymously with
+fn compute_nullifier(context_alias: [u8; 32], form_id: u64) -> [u8; 32] {
+    nullifier_of(&context_alias, form_id)
+}
+
+fn nullifier_of(context_alias: &[u8; 32], form_id: u64) -> [u8; 32] {
+    let mut preimage = [0u8; 40];
+    preimage[..32].copy_from_slice(context_alias);
+    preimage[32..].copy_from_slice(&form_id.to_be_bytes());
+    keccak::hash(&preimage)
+}
+
+/// `keccak256(choices ++ salt)`, one byte per choice field
+fn compute_commitment(choices: Uint8Array, salt: [u8; 32]) -> [u8; 32] {
+    commitment_of(&choices.0, &salt)
+}
+
+fn commitment_of(choices: &[u8], salt: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = choices.to_vec();
+    preimage.extend_from_slice(salt);
+    keccak::hash(&preimage)
+}
+
+/// Switch a form without responses to commit-reveal: responses are commits
+/// until the deadline and are revealed and tallied until `reveal_deadline`.
+/// 0 switches back. Needs a deadline, and isn't available for pseudonymous forms
+/// or unlimited submissions.
+fn set_commit_reveal(form_id: u64, reveal_deadline: u64) -> bool {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    if !RESPONSE_CIDS.at(&form_id).is_empty() {
+        FormHasResponses { form_id }.revert();
+    }
+    if reveal_deadline == 0 {
+        FORM_REVEAL_DEADLINE.remove(&form_id);
+        CommitRevealUpdated { form_id, reveal_deadline }.emit();
+        return true;
+    }
+
+    if FORM_PSEUDONYMOUS.contains(&form_id) {
+        WrongSubmissionMode { form_id, pseudonymous: true }.revert();
+    }
+    let policy = FORM_POLICY.get_or_default(&form_id);
+    if policy == POLICY_UNLIMITED {
+        InvalidSubmissionPolicy { policy }.revert();
+    }
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    if deadline == 0 || reveal_deadline <= deadline {
+        InvalidDeadline { form_id, deadline, requested: reveal_deadline }.revert();
+    }
+
+    FORM_REVEAL_DEADLINE.set(&form_id, &reveal_deadline);
+    CommitRevealUpdated { form_id, reveal_deadline }.emit();
+    true
+}
+
+/// 0 unless the form uses commit-reveal
+fn get_reveal_deadline(form_id: u64) -> u64 {
+    require_form(form_id);
+    FORM_REVEAL_DEADLINE.get_or_default(&form_id)
+}
+
+/// Commitment of `respondent` that is still to be revealed; zero if none
+fn get_commitment(form_id: u64, respondent: [u8; 20]) -> [u8; 32] {
+    require_form(form_id);
+    VOTER_RESPONSE
+        .get(&(form_id, respondent))
+        .and_then(|idx| COMMITMENTS.get(&(form_id, idx)))
+        .unwrap_or_default()
+}
+
+/// Commits not revealed (yet); they are excluded from the aggregates
+fn get_unrevealed_count(form_id: u64) -> u64 {
+    require_form(form_id);
+    UNREVEALED_COUNT.get_or_default(&form_id)
+}
+
+/// Check that the form takes responses and `voter` may respond; returns the
+/// current timestamp and, on forms requiring personhood, the voter's "dotns"
+/// context alias (zero otherwise)
+fn require_accepting(form_id: u64, voter: [u8; 20], proof: &[[u8; 32]], pass_id: Option<U256>) -> (u64, [u8; 32]) {
+    require_form(form_id);
+    match form_status(form_id) {
+        STATUS_OPEN => {}
+        STATUS_CLOSED | STATUS_ARCHIVED => FormIsClosed { form_id }.revert(),
+        STATUS_SCHEDULED => FormNotYetOpen { form_id, opens_at: FORM_OPENS_AT.get_or_default(&form_id) }.revert(),
+        status => FormNotOpen { form_id, status }.revert(),
+    }
+
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    let now = get_timestamp();
+    if deadline > 0 && now > deadline {
+        DeadlinePassed { form_id, deadline }.revert();
+    }
+    if !eligible(form_id, voter, proof) {
+        NotEligible { form_id, respondent: voter }.revert();
+    }
+    if let Some(gate) = FORM_PASS_GATE.get(&form_id) {
+        match pass_id {
+            None => PassRequired { form_id }.revert(),
+            Some(pass_id) if !holds_pass(&gate, voter, pass_id) => InvalidPass { form_id, pass_id }.revert(),
+            Some(_) => {}
+        }
+    }
+    let mut context_alias = [0u8; 32];
+    if let Some(required) = FORM_MIN_PERSONHOOD.get(&form_id) {
+        let status = match personhood::query(&voter, &personhood::DOTNS_CONTEXT) {
+            Ok(person) => {
+                context_alias = person.context_alias;
+                person.status
+            }
+            Err(_) => personhood::STATUS_NONE,
+        };
+        if status < required {
+            PersonhoodRequired { form_id, respondent: voter, required, status }.revert();
+        }
+    }
+    (now, context_alias)
+}
+
+/// One option per choice field, each within that field's option count
+fn require_valid_choices(form_id: u64, choices: &[u8]) {
+    let option_counts = FORM_CHOICES.get_or_default(&form_id);
+    if choices.len() != option_counts.len() {
+        WrongChoiceCount { form_id, expected: option_counts.len() as u64, given: choices.len() as u64 }.revert();
+    }
+    if let Some(field_idx) = choices.iter().zip(&option_counts).position(|(choice, count)| choice >= count) {
+        InvalidChoice { form_id, field_idx: field_idx as u8, option_idx: choices[field_idx] }.revert();
+    }
+}
+
+/// Switch a form without responses between regular and pseudonymous
+/// (one-per-person) submissions. Pseudonymous forms need a personhood
+/// requirement, which binds each nullifier to a person.
+fn set_pseudonymous(form_id: u64, pseudonymous: bool) -> bool {
+    require_creator(form_id);
+    require_not_archived(form_id);
+    if !RESPONSE_CIDS.at(&form_id).is_empty() {
+        FormHasResponses { form_id }.revert();
+    }
+    if pseudonymous && !FORM_MIN_PERSONHOOD.contains(&form_id) {
+        PseudonymityRequiresPersonhood { form_id }.revert();
+    }
+    if pseudonymous && FORM_REVEAL_DEADLINE.contains(&form_id) {
+        WrongCommitRevealMode { form_id, commit_reveal: true }.revert();
+    }
+    if pseudonymous {
+        FORM_PSEUDONYMOUS.set(&form_id, &true);
+    } else {
+        FORM_PSEUDONYMOUS.remove(&form_id);
+    }
+    PseudonymityUpdated { form_id, pseudonymous }.emit();
+    true
+}
+
+fn is_pseudonymous(form_id: u64) -> bool {
+    require_form(form_id);
+    FORM_PSEUDONYMOUS.contains(&form_id)
+}
+
+fn is_nullifier_spent(form_id: u64, nullifier: [u8; 32]) -> bool {
+    require_form(form_id);
+    SPENT_NULLIFIERS.contains(&(form_id, nullifier))
+}
+
+fn emit_submission(form_id: u64, respondent: [u8; 20], response_idx: u64, cid: Vec<u8>, choices: Vec<u8>, timestamp: u64) {
+    ResponseSubmitted { form_id, respondent, response_idx, cid, timestamp }.emit();
+    if !choices.is_empty() {
+        AggregateRecorded { form_id, respondent, choices: Uint8Array(choices) }.emit();
+    }
+}
+
+fn get_response_cid(form_id: u64, submission_idx: u64) -> Vec<u8> {
+    require_form(form_id);
+    RESPONSE_CIDS.at(&form_id).get(submission_idx).unwrap_or_default()
+}
+
+/// Schema version the response answered; 0 for unknown responses
+fn get_response_schema_version(form_id: u64, submission_idx: u64) -> u64 {
+    require_form(form_id);
+    RESPONSE_SCHEMA_VERSION.get_or_default(&(form_id, submission_idx))
+}
+
+/// Up to `limit` responses from `offset` as `(idx, respondent, timestamp,
+/// cid)`; pseudonymous responses have a zero respondent. A page also ends once
+/// its return data would exceed `MAX_PAGE_BYTES`, so keep paging from
+/// `offset` plus the page length until `getResponseCount`.
+fn get_responses(form_id: u64, offset: u64, limit: u64) -> Vec<(u64, [u8; 20], u64, Vec<u8>)> {
+    require_form(form_id);
+    let responses = RESPONSE_CIDS.at(&form_id);
+    let end = offset.saturating_add(limit).min(responses.len());
+    let mut page = Vec::new();
+    let mut size = 64; // offset and length of the array
+    for idx in offset..end {
+        let cid = responses.get(idx).unwrap_or_default();
+        // Entry offset, tuple head, cid length and padded cid
+        size += 6 * 32 + cid.len().div_ceil(32) * 32;
+        // Always return at least one response so paging makes progress
+        if size > MAX_PAGE_BYTES && !page.is_empty() {
+            break;
+        }
+        let meta = RESPONSE_META.get_or_default(&(form_id, idx));
+        page.push((idx, meta.respondent, meta.timestamp, cid));
+    }
+    page
+}
+
+/// Add one vote per choice field; `choices` is already validated
+fn tally(form_id: u64, choices: &[u8]) {
+    for (field_idx, option_idx) in choices.iter().enumerate() {
+        let key = (form_id, field_idx as u8, *option_idx);
+        AGGREGATES.set(&key, &(AGGREGATES.get_or_default(&key) + 1));
+    }
+}
+
+fn untally(form_id: u64, choices: &[u8]) {
+    for (field_idx, option_idx) in choices.iter().enumerate() {
+        let key = (form_id, field_idx as u8, *option_idx);
+        AGGREGATES.set(&key, &AGGREGATES.get_or_default(&key).saturating_sub(1));
+    }
+}
+
+/// Aggregates are only recorded through `submitResponseWithChoices`, so
+/// each respondent contributes exactly one tally per choice field
+fn record_aggregate(_form_id: u64, _field_idx: u8, _option_idx: u8) -> bool {
+    errors::revert("Use submitResponseWithChoices")
+}
+
+fn get_choice_option_counts(form_id: u64) -> Uint8Array {
+    require_form(form_id);
+    Uint8Array(FORM_CHOICES.get_or_default(&form_id))
+}
+
+fn get_aggregate_count(form_id: u64, field_idx: u8, option_idx: u8) -> u64 {
+    require_form(form_id);
+    AGGREGATES.get_or_default(&(form_id, field_idx, option_idx))
+}
+
+fn has_submitted(form_id: u64, voter: [u8; 20]) -> bool {
+    require_form(form_id);
+    VOTER_RESPONSE.contains(&(form_id, voter))
+}
+
+fn get_submission_policy(form_id: u64) -> u8 {
+    require_form(form_id);
+    FORM_POLICY.get_or_default(&form_id)
+}
+
+/// Creator of an existing form; reverts for unknown and deleted IDs
+fn require_form(form_id: u64) -> [u8; 20] {
+    match FORM_CREATOR.get(&form_id) {
+        Some(creator) if FORM_STATUS.get_or_default(&form_id) != STATUS_DELETED => creator,
+        _ => FormNotFound { form_id }.revert(),
+    }
+}
+
+fn require_not_archived(form_id: u64) {
+    if FORM_STATUS.get_or_default(&form_id) == STATUS_ARCHIVED {
+        FormIsArchived { form_id }.revert();
+    }
+}
+
+/// Lifecycle status as reported to clients: an open form whose `opensAt` is
+/// still ahead is scheduled
+fn form_status(form_id: u64) -> u8 {
+    let status = FORM_STATUS.get_or_default(&form_id);
+    if status == STATUS_OPEN && get_timestamp() < FORM_OPENS_AT.get_or_default(&form_id) {
+        return STATUS_SCHEDULED;
+    }
+    status
+}
+
+/// Creator of the form, if it may move from its current status to `target`
+fn require_transition(form_id: u64, target: u8, allowed_from: &[u8]) -> [u8; 20] {
+    let caller = require_creator(form_id);
+    let status = form_status(form_id);
+    if !allowed_from.contains(&status) {
+        InvalidStatusTransition { form_id, status, target }.revert();
+    }
+    caller
+}
+
+fn set_status(form_id: u64, changed_by: [u8; 20], status: u8) {
+    FORM_STATUS.set(&form_id, &status);
+    FormStatusChanged { form_id, changed_by, status: form_status(form_id), timestamp: get_timestamp() }.emit();
+}
+
+/// `(title, description, fieldsJson, deadline, createdAt, creator, status,
+/// encryptionPubKey, submissionPolicy, responseCount)`
+type FormView = (String, String, Vec<u8>, u64, u64, [u8; 20], u8, Vec<u8>, u8, u64);
+
+fn get_form(form_id: u64) -> FormView {
+    let creator = require_form(form_id);
+    (
+        FORM_TITLE.get_or_default(&form_id),
+        FORM_DESCRIPTION.get_or_default(&form_id),
+        latest_fields(form_id),
+        FORM_DEADLINE.get_or_default(&form_id),
+        FORM_CREATED_AT.get_or_default(&form_id),
+        creator,
+        form_status(form_id),
+        FORM_PUBKEY.get_or_default(&form_id),
+        FORM_POLICY.get_or_default(&form_id),
+        RESPONSE_CIDS.at(&form_id).len(),
+    )
+}
+
+fn get_form_title(form_id: u64) -> String {
+    require_form(form_id);
+    FORM_TITLE.get_or_default(&form_id)
+}
+
+fn get_form_description(form_id: u64) -> String {
+    require_form(form_id);
+    FORM_DESCRIPTION.get_or_default(&form_id)
+}
+
+fn latest_fields(form_id: u64) -> Vec<u8> {
+    let versions = FORM_FIELDS.at(&form_id);
+    versions.get(versions.len().saturating_sub(1)).unwrap_or_default()
+}
+
+/// Current fields_json
+fn get_form_fields(form_id: u64) -> Vec<u8> {
+    require_form(form_id);
+    latest_fields(form_id)
+}
+
+fn get_form_schema_version(form_id: u64) -> u64 {
+    require_form(form_id);
+    FORM_FIELDS.at(&form_id).len()
+}
+
+/// fields_json as of schema `version` (1-based)
+fn get_form_fields_version(form_id: u64, version: u64) -> Vec<u8> {
+    require_form(form_id);
+    match version.checked_sub(1).and_then(|idx| FORM_FIELDS.at(&form_id).get(idx)) {
+        Some(fields) => fields,
+        None => errors::panic(errors::PANIC_ARRAY_OUT_OF_BOUNDS),
+    }
+}
+
+fn get_form_deadline(form_id: u64) -> u64 {
+    require_form(form_id);
+    FORM_DEADLINE.get_or_default(&form_id)
+}
+
+fn get_form_created_at(form_id: u64) -> u64 {
+    require_form(form_id);
+    FORM_CREATED_AT.get_or_default(&form_id)
+}
+
+fn get_form_creator(form_id: u64) -> [u8; 20] {
+    require_form(form_id)
+}
+
+/// Lifecycle status; deleted forms keep reporting STATUS_DELETED
+fn get_form_status(form_id: u64) -> u8 {
+    if !FORM_CREATOR.contains(&form_id) {
+        FormNotFound { form_id }.revert();
+    }
+    form_status(form_id)
+}
+
+fn get_form_opens_at(form_id: u64) -> u64 {
+    require_form(form_id);
+    FORM_OPENS_AT.get_or_default(&form_id)
+}
+
+/// Whether `submitResponse` currently accepts responses for the form
+fn is_form_open(form_id: u64) -> bool {
+    require_form(form_id);
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    form_status(form_id) == STATUS_OPEN
+        && (deadline == 0 || get_timestamp() <= deadline)
+}
+
+fn get_form_pub_key(form_id: u64) -> Vec<u8> {
+    require_form(form_id);
+    FORM_PUBKEY.get_or_default(&form_id)
+}
+
+/// Publish a draft; responses are accepted from `opens_at` on, or right
+/// away if it is 0 or already past
+fn publish_form(form_id: u64, opens_at: u64) -> bool {
+    let caller = require_transition(form_id, STATUS_OPEN, &[STATUS_DRAFT]);
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    if deadline > 0 && opens_at.max(get_timestamp()) >= deadline {
+        InvalidOpeningTime { form_id, opens_at, deadline }.revert();
+    }
+
+    FORM_OPENS_AT.set(&form_id, &opens_at);
+    set_status(form_id, caller, STATUS_OPEN);
+    true
+}
+
+/// Only the creator can close an open or scheduled form, and only once
+fn close_form(form_id: u64) -> bool {
+    let caller = require_creator(form_id);
+    match form_status(form_id) {
+        STATUS_OPEN | STATUS_SCHEDULED => {}
+        STATUS_CLOSED | STATUS_ARCHIVED => FormIsClosed { form_id }.revert(),
+        status => InvalidStatusTransition { form_id, status, target: STATUS_CLOSED }.revert(),
+    }
+
+    set_status(form_id, caller, STATUS_CLOSED);
+    FormClosed { form_id, closed_by: caller, timestamp: get_timestamp() }.emit();
+    true
+}
+
+/// Accept responses again; a passed deadline must be extended first
+fn reopen_form(form_id: u64) -> bool {
+    let caller = require_transition(form_id, STATUS_OPEN, &[STATUS_CLOSED]);
+    let deadline = FORM_DEADLINE.get_or_default(&form_id);
+    if deadline > 0 && get_timestamp() > deadline {
+        DeadlinePassed { form_id, deadline }.revert();
+    }
+
+    set_status(form_id, caller, STATUS_OPEN);
+    true
+}
+
+/// Freeze a closed form: responses and tallies stay readable, but the form
+/// can no longer be edited or reopened
+fn archive_form(form_id: u64) -> bool {
+    let caller = require_transition(form_id, STATUS_ARCHIVED, &[STATUS_CLOSED]);
+    set_status(form_id, caller, STATUS_ARCHIVED);
+    true
+}
+
+/// Delete a draft, closed or archived form and drop its metadata. Responses,
+/// tallies and schema versions can be any size, so they stay behind for
+/// `purgeForm` to reclaim in batches. Per-respondent records can't be
+/// enumerated and stay behind too, and the ID is never reused.
+fn delete_form(form_id: u64) -> bool {
+    let caller = require_transition(form_id, STATUS_DELETED, &[STATUS_DRAFT, STATUS_CLOSED, STATUS_ARCHIVED]);
+
+    FORM_TITLE.remove(&form_id);
+    FORM_DESCRIPTION.remove(&form_id);
+    FORM_PUBKEY.remove(&form_id);
+    FORM_DEADLINE.remove(&form_id);
+    FORM_OPENS_AT.remove(&form_id);
+    FORM_CREATED_AT.remove(&form_id);
+    FORM_POLICY.remove(&form_id);
+    ALLOWLIST_SIZE.remove(&form_id);
+    FORM_MERKLE_ROOT.remove(&form_id);
+    FORM_PASS_GATE.remove(&form_id);
+    FORM_MIN_PERSONHOOD.remove(&form_id);
+    FORM_PSEUDONYMOUS.remove(&form_id);
+    FORM_REVEAL_DEADLINE.remove(&form_id);
+    UNREVEALED_COUNT.remove(&form_id);
+
+    set_status(form_id, caller, STATUS_DELETED);
+    true
+}
+
+/// Reclaim up to `max_items` responses, tallies and schema versions of a
+/// deleted form, newest first; returns how many are left. Call it until it
+/// returns 0.
+fn purge_form(form_id: u64, max_items: u64) -> u64 {
+    let caller = get_caller();
+    match FORM_CREATOR.get(&form_id) {
+        Some(creator) if creator == caller => {}
+        Some(_) => NotFormCreator { form_id, caller }.revert(),
+        None => FormNotFound { form_id }.revert(),
+    }
+    let status = FORM_STATUS.get_or_default(&form_id);
+    if status != STATUS_DELETED {
+        FormNotDeleted { form_id, status }.revert();
+    }
+
+    let mut budget = max_items;
+    let responses = RESPONSE_CIDS.at(&form_id);
+    while budget > 0 && !responses.is_empty() {
+        let idx = responses.len() - 1;
+        responses.pop();
+        RESPONSE_SCHEMA_VERSION.remove(&(form_id, idx));
+        RESPONSE_META.remove(&(form_id, idx));
+        COMMITMENTS.remove(&(form_id, idx));
+        budget -= 1;
+    }
+
+    // Tallies are dropped from the last option of the last field, shrinking
+    // the stored option counts so the next call picks up where this one ended
+    let mut option_counts = FORM_CHOICES.get_or_default(&form_id);
+    while budget > 0 {
+        let field_idx = match option_counts.len() {
+            0 => break,
+            len => len - 1,
+        };
+        if option_counts[field_idx] == 0 {
+            option_counts.pop();
+            continue;
+        }
+        option_counts[field_idx] -= 1;
+        AGGREGATES.remove(&(form_id, field_idx as u8, option_counts[field_idx]));
+        budget -= 1;
+    }
+    if option_counts.is_empty() {
+        FORM_CHOICES.remove(&form_id);
+    } else {
+        FORM_CHOICES.set(&form_id, &option_counts);
+    }
+
+    let fields = FORM_FIELDS.at(&form_id);
+    while budget > 0 && fields.pop().is_some() {
+        budget -= 1;
+    }
+
+    let tallies: u64 = option_counts.iter().map(|count| *count as u64).sum();
+    responses.len() + tallies + fields.len()
+}
+
+fn get_response_count(form_id: u64) -> u64 {
+    require_form(form_id);
+    RESPONSE_CIDS.at(&form_id).len()
+}
+
+fn form_count() -> u64 {
+    FORM_COUNT.get_or_default()
+}
+
+// ============ Dispatcher ============
+
+// Selectors are keccak256 of each signature, computed at compile time
+dispatch! {
+    fn route;
+
+    #[selector("createForm(string,string,bytes,uint64,bytes)")]
+    fn create_form(title: String, description: String, fields_json: Vec<u8>, deadline: u64, encryption_pub_key: Vec<u8>) -> u64;
+
+    #[selector("createForm(string,string,bytes,uint64,bytes,uint8)")]
+    fn create_form_with_policy(title: String, description: String, fields_json: Vec<u8>, deadline: u64, encryption_pub_key: Vec<u8>, policy: u8) -> u64;
+
+    #[selector("createForm(string,string,bytes,uint64,bytes,uint8,uint8[])")]
+    fn create_form_with_choices(title: String, description: String, fields_json: Vec<u8>, deadline: u64, encryption_pub_key: Vec<u8>, policy: u8, option_counts: Uint8Array) -> u64;
+
+    #[selector("createForm(string,string,bytes,uint64,bytes,uint8,uint8[],address[])")]
+    fn create_form_with_allowlist(title: String, description: String, fields_json: Vec<u8>, deadline: u64, encryption_pub_key: Vec<u8>, policy: u8, option_counts: Uint8Array, allowlist: Vec<[u8; 20]>) -> u64;
+
+    #[selector("createDraft(string,string,bytes,uint64,bytes,uint8,uint8[])")]
+    fn create_draft(title: String, description: String, fields_json: Vec<u8>, deadline: u64, encryption_pub_key: Vec<u8>, policy: u8, option_counts: Uint8Array) -> u64;
+
+    #[selector("getChoiceOptionCounts(uint64)")]
+    fn get_choice_option_counts(form_id: u64) -> Uint8Array;
+
+    #[selector("getSubmissionPolicy(uint64)")]
+    fn get_submission_policy(form_id: u64) -> u8;
+
+    #[selector("addToAllowlist(uint64,address[])")]
+    fn add_to_allowlist(form_id: u64, respondents: Vec<[u8; 20]>) -> u64;
+
+    #[selector("removeFromAllowlist(uint64,address[])")]
+    fn remove_from_allowlist(form_id: u64, respondents: Vec<[u8; 20]>) -> u64;
+
+    #[selector("isEligible(uint64,address)")]
+    fn is_eligible(form_id: u64, respondent: [u8; 20]) -> bool;
+
+    #[selector("isEligible(uint64,address,bytes32[])")]
+    fn is_eligible_with_proof(form_id: u64, respondent: [u8; 20], proof: Vec<[u8; 32]>) -> bool;
+
+    #[selector("isEligible(uint64,address,bytes32[],uint256)")]
+    fn is_eligible_with_pass(form_id: u64, respondent: [u8; 20], proof: Vec<[u8; 32]>, pass_id: U256) -> bool;
+
+    #[selector("setPassGate(uint64,address,string,string)")]
+    fn set_pass_gate(form_id: u64, access_pass: [u8; 20], location_id: String, access_level: String) -> bool;
+
+    #[selector("getPassGate(uint64)")]
+    fn get_pass_gate(form_id: u64) -> ([u8; 20], String, String);
+
+    #[selector("setPersonhoodRequirement(uint64,uint8)")]
+    fn set_personhood_requirement(form_id: u64, min_status: u8) -> bool;
+
+    #[selector("getPersonhoodRequirement(uint64)")]
+    fn get_personhood_requirement(form_id: u64) -> u8;
+
+    #[selector("setMerkleRoot(uint64,bytes32)")]
+    fn set_merkle_root(form_id: u64, root: [u8; 32]) -> bool;
+
+    #[selector("getMerkleRoot(uint64)")]
+    fn get_merkle_root(form_id: u64) -> [u8; 32];
+
+    #[selector("hasAllowlist(uint64)")]
+    fn has_allowlist(form_id: u64) -> bool;
+
+    #[selector("getAllowlistSize(uint64)")]
+    fn get_allowlist_size(form_id: u64) -> u64;
+
+    #[selector("submitResponse(uint64,bytes)")]
+    fn submit_response(form_id: u64, cid: Vec<u8>) -> u64;
+
+    #[selector("submitResponseWithChoices(uint64,bytes,uint8[])")]
+    fn submit_response_with_choices(form_id: u64, cid: Vec<u8>, choices: Uint8Array) -> u64;
+
+    #[selector("submitResponseWithProof(uint64,bytes,uint8[],bytes32[])")]
+    fn submit_response_with_proof(form_id: u64, cid: Vec<u8>, choices: Uint8Array, proof: Vec<[u8; 32]>) -> u64;
+
+    #[selector("submitResponseWithPass(uint64,bytes,uint8[],bytes32[],uint256)")]
+    fn submit_response_with_pass(form_id: u64, cid: Vec<u8>, choices: Uint8Array, proof: Vec<[u8; 32]>, pass_id: U256) -> u64;
+
+    #[selector("submitPseudonymousResponse(uint64,bytes32,bytes,uint8[])")]
+    fn submit_pseudonymous_response(form_id: u64, nullifier: [u8; 32], cid: Vec<u8>, choices: Uint8Array) -> u64;
+
+    #[selector("submitPseudonymousResponse(uint64,bytes32,bytes,uint8[],bytes32[],uint256)")]
+    fn submit_pseudonymous_response_with_pass(form_id: u64, nullifier: [u8; 32], cid: Vec<u8>, choices: Uint8Array, proof: Vec<[u8; 32]>, pass_id: U256) -> u64;
+
+    #[selector("commitResponse(uint64,bytes32,bytes)")]
+    fn commit_response(form_id: u64, commitment: [u8; 32], cid: Vec<u8>) -> u64;
+
+    #[selector("commitResponse(uint64,bytes32,bytes,bytes32[],uint256)")]
+    fn commit_response_with_pass(form_id: u64, commitment: [u8; 32], cid: Vec<u8>, proof: Vec<[u8; 32]>, pass_id: U256) -> u64;
+
+    #[selector("revealResponse(uint64,uint8[],bytes32)")]
+    fn reveal_response(form_id: u64, choices: Uint8Array, salt: [u8; 32]) -> bool;
+
+    #[selector("computeNullifier(bytes32,uint64)")]
+    fn compute_nullifier(context_alias: [u8; 32], form_id: u64) -> [u8; 32];
+
+    #[selector("computeCommitment(uint8[],bytes32)")]
+    fn compute_commitment(choices: Uint8Array, salt: [u8; 32]) -> [u8; 32];
+
+    #[selector("setCommitReveal(uint64,uint64)")]
+    fn set_commit_reveal(form_id: u64, reveal_deadline: u64) -> bool;
+
+    #[selector("getRevealDeadline(uint64)")]
+    fn get_reveal_deadline(form_id: u64) -> u64;
+
+    #[selector("getCommitment(uint64,address)")]
+    fn get_commitment(form_id: u64, respondent: [u8; 20]) -> [u8; 32];
+
+    #[selector("getUnrevealedCount(uint64)")]
+    fn get_unrevealed_count(form_id: u64) -> u64;
+
+    #[selector("setPseudonymous(uint64,bool)")]
+    fn set_pseudonymous(form_id: u64, pseudonymous: bool) -> bool;
+
+    #[selector("isPseudonymous(uint64)")]
+    fn is_pseudonymous(form_id: u64) -> bool;
+
+    #[selector("isNullifierSpent(uint64,bytes32)")]
+    fn is_nullifier_spent(form_id: u64, nullifier: [u8; 32]) -> bool;
+
+    #[selector("getResponseCid(uint64,uint64)")]
+    fn get_response_cid(form_id: u64, submission_idx: u64) -> Vec<u8>;
+
+    #[selector("getResponseSchemaVersion(uint64,uint64)")]
+    fn get_response_schema_version(form_id: u64, submission_idx: u64) -> u64;
+
+    #[selector("getResponses(uint64,uint64,uint64)")]
+    fn get_responses(form_id: u64, offset: u64, limit: u64) -> Vec<(u64, [u8; 20], u64, Vec<u8>)>;
+
+    #[selector("recordAggregate(uint64,uint8,uint8)")]
+    fn record_aggregate(form_id: u64, field_idx: u8, option_idx: u8) -> bool;
+
+    #[selector("getAggregateCount(uint64,uint8,uint8)")]
+    fn get_aggregate_count(form_id: u64, field_idx: u8, option_idx: u8) -> u64;
+
+    #[selector("hasSubmitted(uint64,address)")]
+    fn has_submitted(form_id: u64, voter: [u8; 20]) -> bool;
+
+    #[selector("publishForm(uint64,uint64)")]
+    fn publish_form(form_id: u64, opens_at: u64) -> bool;
+
+    #[selector("closeForm(uint64)")]
+    fn close_form(form_id: u64) -> bool;
+
+    #[selector("reopenForm(uint64)")]
+    fn reopen_form(form_id: u64) -> bool;
+
+    #[selector("archiveForm(uint64)")]
+    fn archive_form(form_id: u64) -> bool;
+
+    #[selector("deleteForm(uint64)")]
+    fn delete_form(form_id: u64) -> bool;
+
+    #[selector("purgeForm(uint64,uint64)")]
+    fn purge_form(form_id: u64, max_items: u64) -> u64;
+
+    #[selector("updateForm(uint64,string,string,bytes)")]
+    fn update_form(form_id: u64, title: String, description: String, fields_json: Vec<u8>) -> u64;
+
+    #[selector("updateForm(uint64,string,string,bytes,uint8[])")]
+    fn update_form_with_choices(form_id: u64, title: String, description: String, fields_json: Vec<u8>, option_counts: Uint8Array) -> u64;
+
+    #[selector("extendDeadline(uint64,uint64)")]
+    fn extend_deadline(form_id: u64, new_deadline: u64) -> bool;
+
+    #[selector("getForm(uint64)")]
+    fn get_form(form_id: u64) -> FormView;
+
+    #[selector("getFormTitle(uint64)")]
+    fn get_form_title(form_id: u64) -> String;
+
+    #[selector("getFormDescription(uint64)")]
+    fn get_form_description(form_id: u64) -> String;
+
+    #[selector("getFormFields(uint64)")]
+    fn get_form_fields(form_id: u64) -> Vec<u8>;
+
+    #[selector("getFormSchemaVersion(uint64)")]
+    fn get_form_schema_version(form_id: u64) -> u64;
+
+    #[selector("getFormFields(uint64,uint64)")]
+    fn get_form_fields_version(form_id: u64, version: u64) -> Vec<u8>;
+
+    #[selector("getFormDeadline(uint64)")]
+    fn get_form_deadline(form_id: u64) -> u64;
+
+    #[selector("getFormCreatedAt(uint64)")]
+    fn get_form_created_at(form_id: u64) -> u64;
+
+    #[selector("getFormCreator(uint64)")]
+    fn get_form_creator(form_id: u64) -> [u8; 20];
+
+    #[selector("getFormStatus(uint64)")]
+    fn get_form_status(form_id: u64) -> u8;
+
+    #[selector("getFormOpensAt(uint64)")]
+    fn get_form_opens_at(form_id: u64) -> u64;
+
+    #[selector("isFormOpen(uint64)")]
+    fn is_form_open(form_id: u64) -> bool;
+
+    #[selector("getFormPubKey(uint64)")]
+    fn get_form_pub_key(form_id: u64) -> Vec<u8>;
+
+    #[selector("formCount()")]
+    fn form_count() -> u64;
+
+    #[selector("getResponseCount(uint64)")]
+    fn get_response_count(form_id: u64) -> u64;
+}
+
+#[no_mangle]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
+pub extern "C" fn call() {
+    dispatch();
+}
+
+/// Selector dispatch behind `call()`, kept as a Rust-ABI function so the
+/// mock host can unwind out of `return_value` in tests.
+fn dispatch() {
+    let length = api::call_data_size() as usize;
+    // Like a Solidity contract without a fallback, bad calldata reverts with empty data
+    if length < 4 {
+        errors::revert_with(&[]);
+    }
+
+    let mut selector = [0u8; 4];
+    api::call_data_copy(&mut selector, 0);
+
+    require_max_len("callData", length, MAX_CALL_DATA_LEN);
+    let mut data = alloc::vec![0u8; length - 4];
+    api::call_data_copy(&mut data, 4);
+
+    match route(selector, &data) {
+        Ok(output) => api::return_value(ReturnFlags::empty(), &output),
+        Err(DispatchError::UnknownSelector | DispatchError::Decode) => errors::revert_with(&[]),
+    }
+}
+
+#[no_mangle]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
+pub extern "C" fn deploy() {
+    // No constructor logic needed
+}
+
+#[cfg(feature = "mock")]
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::{decode, encode};
+    use intran3t_polkavm_contracts::mock::{self, address, bytes, bytes32, call, query, string, uint};
+
+    const CREATOR: [u8; 20] = [0x11; 20];
+    const ALICE: [u8; 20] = [0x22; 20];
+    const BOB: [u8; 20] = [0x33; 20];
+    const CAROL: [u8; 20] = [0x44; 20];
+    const NOW: u64 = 1_000;
+
+    fn setup() {
+        mock::reset();
+        mock::deploy(dispatch, HEAP_SIZE);
+        mock::set_caller(CREATOR);
+        mock::set_now(NOW);
+    }
+
+    fn choices(values: &[u8]) -> Token {
+        Token::Array(values.iter().map(|value| uint(*value as u64)).collect())
+    }
+
+    /// Open form with `policy` and one choice field per `option_counts` entry
+    fn create_form(deadline: u64, policy: u8, option_counts: &[u8]) -> u64 {
+        query(
+            "createForm(string,string,bytes,uint64,bytes,uint8,uint8[])",
+            &[
+                string("Title"),
+                string("Description"),
+                bytes(b"[]"),
+                uint(deadline),
+                bytes(&[4; 65]),
+                uint(policy as u64),
+                choices(option_counts),
+            ],
+        )
+    }
+
+    fn submit(form_id: u64, cid: &[u8], picked: &[u8]) -> mock::Outcome {
+        call("submitResponseWithChoices(uint64,bytes,uint8[])", &[uint(form_id), bytes(cid), choices(picked)])
+    }
+
+    fn response_count(form_id: u64) -> u64 {
+        query("getResponseCount(uint64)", &[uint(form_id)])
+    }
+
+    fn aggregate(form_id: u64, field_idx: u8, option_idx: u8) -> u64 {
+        query(
+            "getAggregateCount(uint64,uint8,uint8)",
+            &[uint(form_id), uint(field_idx as u64), uint(option_idx as u64)],
+        )
+    }
+
+    fn topics() -> Vec<[u8; 32]> {
+        mock::take_events().into_iter().map(|event| event.topics[0]).collect()
+    }
+
+    #[test]
+    fn create_form_and_read_it_back() {
+        setup();
+        let form_id: u64 = query(
+            "createForm(string,string,bytes,uint64,bytes)",
+            &[string("Survey"), string("About us"), bytes(&[b'x'; 1000]), uint(2_000), bytes(&[1; 32])],
+        );
+        assert_eq!(form_id, 1);
+        assert_eq!(query::<u64>("formCount()", &[]), 1);
+        assert_eq!(topics(), [FormCreated::TOPIC]);
+
+        let form: FormView = query("getForm(uint64)", &[uint(form_id)]);
+        assert_eq!(form.0, "Survey");
+        assert_eq!(form.1, "About us");
+        assert_eq!(form.2, [b'x'; 1000]);
+        assert_eq!((form.3, form.4), (2_000, NOW));
+        assert_eq!(form.5, CREATOR);
+        assert_eq!(form.6, STATUS_OPEN);
+        assert_eq!(form.7, [1; 32]);
+        assert_eq!((form.8, form.9), (POLICY_SINGLE, 0));
+        assert_eq!(query::<String>("getFormTitle(uint64)", &[uint(form_id)]), "Survey");
+        assert_eq!(query::<[u8; 20]>("getFormCreator(uint64)", &[uint(form_id)]), CREATOR);
+    }
+
+    #[test]
+    fn submit_response_stores_the_cid() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[]);
+        mock::take_events();
+
+        mock::set_caller(ALICE);
+        let out = call("submitResponse(uint64,bytes)", &[uint(form_id), bytes(b"bafy-alice")]);
+        assert_eq!(out.data, encode(&[uint(0)]));
+        assert_eq!(topics(), [ResponseSubmitted::TOPIC]);
+        assert_eq!(response_count(form_id), 1);
+        assert_eq!(query::<Vec<u8>>("getResponseCid(uint64,uint64)", &[uint(form_id), uint(0)]), b"bafy-alice");
+        assert!(query::<bool>("hasSubmitted(uint64,address)", &[uint(form_id), address(ALICE)]));
+        assert!(!query::<bool>("hasSubmitted(uint64,address)", &[uint(form_id), address(BOB)]));
+    }
+
+    #[test]
+    fn unknown_forms_revert() {
+        setup();
+        let out = call("getForm(uint64)", &[uint(7)]);
+        assert!(out.reverted);
+        assert_eq!(out.data, FormNotFound { form_id: 7 }.encode());
+        assert_eq!(submit(7, b"cid", &[]).data, FormNotFound { form_id: 7 }.encode());
+    }
+
+    #[test]
+    fn every_view_reverts_for_unknown_and_deleted_forms() {
+        setup();
+        let deleted = create_form(0, POLICY_SINGLE, &[2]);
+        assert!(query::<bool>("closeForm(uint64)", &[uint(deleted)]));
+        assert!(query::<bool>("deleteForm(uint64)", &[uint(deleted)]));
+
+        // Each view with its arguments after the form ID
+        let views = [
+            ("getForm(uint64)", vec![]),
+            ("getFormTitle(uint64)", vec![]),
+            ("getFormDescription(uint64)", vec![]),
+            ("getFormFields(uint64)", vec![]),
+            ("getFormFields(uint64,uint64)", vec![uint(1)]),
+            ("getFormSchemaVersion(uint64)", vec![]),
+            ("getFormDeadline(uint64)", vec![]),
+            ("getFormCreatedAt(uint64)", vec![]),
+            ("getFormCreator(uint64)", vec![]),
+            ("getFormOpensAt(uint64)", vec![]),
+            ("getFormPubKey(uint64)", vec![]),
+            ("isFormOpen(uint64)", vec![]),
+            ("getSubmissionPolicy(uint64)", vec![]),
+            ("getChoiceOptionCounts(uint64)", vec![]),
+            ("getAggregateCount(uint64,uint8,uint8)", vec![uint(0), uint(0)]),
+            ("getResponseCount(uint64)", vec![]),
+            ("getResponseCid(uint64,uint64)", vec![uint(0)]),
+            ("getResponseSchemaVersion(uint64,uint64)", vec![uint(0)]),
+            ("getResponses(uint64,uint64,uint64)", vec![uint(0), uint(1)]),
+            ("hasSubmitted(uint64,address)", vec![address(ALICE)]),
+            ("isEligible(uint64,address)", vec![address(ALICE)]),
+            ("isEligible(uint64,address,bytes32[])", vec![address(ALICE), Token::Array(Vec::new())]),
+            ("isEligible(uint64,address,bytes32[],uint256)", vec![address(ALICE), Token::Array(Vec::new()), uint(1)]),
+            ("hasAllowlist(uint64)", vec![]),
+            ("getAllowlistSize(uint64)", vec![]),
+            ("getMerkleRoot(uint64)", vec![]),
+            ("getPassGate(uint64)", vec![]),
+            ("getPersonhoodRequirement(uint64)", vec![]),
+            ("isPseudonymous(uint64)", vec![]),
+            ("isNullifierSpent(uint64,bytes32)", vec![bytes32([7; 32])]),
+            ("getRevealDeadline(uint64)", vec![]),
+            ("getCommitment(uint64,address)", vec![address(ALICE)]),
+            ("getUnrevealedCount(uint64)", vec![]),
+            ("getFormStatus(uint64)", vec![]),
+        ];
+        for (signature, rest) in views {
+            let args = |form_id: u64| [vec![uint(form_id)], rest.clone()].concat();
+            let unknown = call(signature, &args(9));
+            assert_eq!(unknown.data, FormNotFound { form_id: 9 }.encode(), "{signature}");
+            // A deleted form keeps only its status
+            if signature != "getFormStatus(uint64)" {
+                let out = call(signature, &args(deleted));
+                assert_eq!(out.data, FormNotFound { form_id: deleted }.encode(), "{signature}");
+            }
+        }
+    }
+
+    #[test]
+    fn bad_calldata_reverts_with_empty_data() {
+        setup();
+        assert_eq!(mock::execute(dispatch, &[1, 2]), mock::Outcome { reverted: true, data: Vec::new() });
+        assert_eq!(call("noSuchFunction()", &[]), mock::Outcome { reverted: true, data: Vec::new() });
+        // Truncated arguments fail to decode
+        let mut input = selector("getForm(uint64)").to_vec();
+        input.extend([0u8; 8]);
+        assert_eq!(mock::execute(dispatch, &input), mock::Outcome { reverted: true, data: Vec::new() });
+    }
+
+    #[test]
+    fn reverted_calls_leave_no_trace() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[2]);
+        mock::take_events();
+        assert!(query::<bool>("closeForm(uint64)", &[uint(form_id)]));
+        mock::take_events();
+
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"late", &[1]).data, FormIsClosed { form_id }.encode());
+        assert_eq!(response_count(form_id), 0);
+        assert_eq!(aggregate(form_id, 0, 1), 0);
+        assert!(mock::events().is_empty());
+    }
+
+    #[test]
+    fn single_policy_rejects_a_second_response() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[]);
+
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"first", &[]).reverted);
+        let out = submit(form_id, b"second", &[]);
+        assert!(out.reverted);
+        assert_eq!(out.data, AlreadySubmitted { form_id, respondent: ALICE }.encode());
+        assert_eq!(response_count(form_id), 1);
+
+        mock::set_caller(BOB);
+        assert_eq!(submit(form_id, b"bob", &[]).data, encode(&[uint(1)]));
+    }
+
+    #[test]
+    fn editable_policy_replaces_the_response_in_place() {
+        setup();
+        let form_id = create_form(0, POLICY_EDITABLE, &[]);
+        assert_eq!(query::<u8>("getSubmissionPolicy(uint64)", &[uint(form_id)]), POLICY_EDITABLE);
+
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"draft", &[]).data, encode(&[uint(0)]));
+        mock::set_now(NOW + 5);
+        assert_eq!(submit(form_id, b"final", &[]).data, encode(&[uint(0)]));
+        assert_eq!(response_count(form_id), 1);
+        assert_eq!(query::<Vec<u8>>("getResponseCid(uint64,uint64)", &[uint(form_id), uint(0)]), b"final");
+        let page: Vec<(u64, [u8; 20], u64, Vec<u8>)> =
+            query("getResponses(uint64,uint64,uint64)", &[uint(form_id), uint(0), uint(10)]);
+        assert_eq!(page, [(0, ALICE, NOW + 5, b"final".to_vec())]);
+    }
+
+    #[test]
+    fn unlimited_policy_appends_every_response() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+
+        mock::set_caller(ALICE);
+        for idx in 0..3 {
+            assert_eq!(submit(form_id, b"again", &[]).data, encode(&[uint(idx)]));
+        }
+        assert_eq!(response_count(form_id), 3);
+    }
+
+    #[test]
+    fn unknown_policies_are_rejected() {
+        setup();
+        let out = call(
+            "createForm(string,string,bytes,uint64,bytes,uint8)",
+            &[string("Title"), string("Description"), bytes(b"[]"), uint(0), bytes(&[]), uint(3)],
+        );
+        assert!(out.reverted);
+        assert_eq!(out.data, InvalidSubmissionPolicy { policy: 3 }.encode());
+        assert_eq!(query::<u64>("formCount()", &[]), 0);
+    }
+
+    #[test]
+    fn choices_are_validated_against_the_option_counts() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[2, 3]);
+        let counts: Uint8Array = query("getChoiceOptionCounts(uint64)", &[uint(form_id)]);
+        assert_eq!(counts.0, [2, 3]);
+
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"cid", &[0]).data, WrongChoiceCount { form_id, expected: 2, given: 1 }.encode());
+        assert_eq!(submit(form_id, b"cid", &[]).data, WrongChoiceCount { form_id, expected: 2, given: 0 }.encode());
+        assert_eq!(
+            submit(form_id, b"cid", &[0, 3]).data,
+            InvalidChoice { form_id, field_idx: 1, option_idx: 3 }.encode()
+        );
+        assert_eq!(response_count(form_id), 0);
+
+        let out = call(
+            "createForm(string,string,bytes,uint64,bytes,uint8,uint8[])",
+            &[string("t"), string("d"), bytes(b"[]"), uint(0), bytes(&[]), uint(0), choices(&[2, 0])],
+        );
+        assert_eq!(out.data, EmptyChoiceField { field_idx: 1 }.encode());
+    }
+
+    #[test]
+    fn submissions_tally_their_choices() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[2, 3]);
+        mock::take_events();
+
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"alice", &[1, 2]).reverted);
+        let events = mock::take_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].topics[0], AggregateRecorded::TOPIC);
+        assert_eq!(events[1].topics[2][12..], ALICE);
+
+        mock::set_caller(BOB);
+        assert!(!submit(form_id, b"bob", &[1, 0]).reverted);
+        assert_eq!((aggregate(form_id, 0, 0), aggregate(form_id, 0, 1)), (0, 2));
+        assert_eq!((aggregate(form_id, 1, 0), aggregate(form_id, 1, 2)), (1, 1));
+
+        // A rejected resubmission leaves the tallies alone
+        assert!(submit(form_id, b"bob", &[0, 0]).reverted);
+        assert_eq!((aggregate(form_id, 0, 0), aggregate(form_id, 1, 0)), (0, 1));
+    }
+
+    #[test]
+    fn editing_a_response_moves_its_tallies() {
+        setup();
+        let form_id = create_form(0, POLICY_EDITABLE, &[2, 3]);
+
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"first", &[1, 2]).reverted);
+        mock::set_caller(BOB);
+        assert!(!submit(form_id, b"bob", &[1, 2]).reverted);
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"second", &[0, 2]).reverted);
+
+        assert_eq!((aggregate(form_id, 0, 0), aggregate(form_id, 0, 1)), (1, 1));
+        assert_eq!(aggregate(form_id, 1, 2), 2);
+        assert_eq!(response_count(form_id), 2);
+    }
+
+    #[test]
+    fn record_aggregate_always_reverts() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[2]);
+        let out = call("recordAggregate(uint64,uint8,uint8)", &[uint(form_id), uint(0), uint(1)]);
+        assert!(out.reverted);
+        assert_eq!(out.data, errors::error_string("Use submitResponseWithChoices"));
+        assert_eq!(aggregate(form_id, 0, 1), 0);
+    }
+
+    fn allowlist(count: usize) -> Token {
+        let respondent = |i: usize| {
+            let mut respondent = [0u8; 20];
+            respondent[..8].copy_from_slice(&(i as u64 + 1).to_be_bytes());
+            address(respondent)
+        };
+        Token::Array((0..count).map(respondent).collect())
+    }
+
+    fn too_long(field: &str, length: usize, max_length: usize) -> Vec<u8> {
+        InputTooLong { field: field.into(), length: length as u64, max_length: max_length as u64 }.encode()
+    }
+
+    #[test]
+    fn inputs_are_checked_against_their_limits() {
+        setup();
+        let create = |title: usize, description: usize, fields: usize| {
+            call(
+                "createForm(string,string,bytes,uint64,bytes)",
+                &[string(&"t".repeat(title)), string(&"d".repeat(description)), bytes(&vec![b'f'; fields]), uint(0), bytes(&[])],
+            )
+        };
+        assert_eq!(create(MAX_TITLE_LEN + 1, 0, 0).data, too_long("title", MAX_TITLE_LEN + 1, MAX_TITLE_LEN));
+        assert_eq!(
+            create(0, MAX_DESCRIPTION_LEN + 1, 0).data,
+            too_long("description", MAX_DESCRIPTION_LEN + 1, MAX_DESCRIPTION_LEN)
+        );
+        assert_eq!(create(0, 0, MAX_FIELDS_LEN + 1).data, too_long("fieldsJson", MAX_FIELDS_LEN + 1, MAX_FIELDS_LEN));
+        assert!(!create(MAX_TITLE_LEN, MAX_DESCRIPTION_LEN, MAX_FIELDS_LEN).reverted);
+
+        let mut input = selector("formCount()").to_vec();
+        input.resize(MAX_CALL_DATA_LEN + 1, 0);
+        assert_eq!(mock::call_raw(&input).data, too_long("callData", MAX_CALL_DATA_LEN + 1, MAX_CALL_DATA_LEN));
+        input.truncate(MAX_CALL_DATA_LEN);
+        assert_eq!(mock::call_raw(&input).data, encode(&[uint(1)]));
+
+        let out = call("addToAllowlist(uint64,address[])", &[uint(1), allowlist(MAX_ALLOWLIST_BATCH + 1)]);
+        assert_eq!(out.data, too_long("respondents", MAX_ALLOWLIST_BATCH + 1, MAX_ALLOWLIST_BATCH));
+    }
+
+    /// The largest call of each kind, so `execute` checks its heap use
+    #[test]
+    fn the_largest_calls_fit_the_heap() {
+        setup();
+        let title = "t".repeat(MAX_TITLE_LEN);
+        let description = "d".repeat(MAX_DESCRIPTION_LEN);
+        let fields = vec![b'f'; MAX_FIELDS_LEN];
+        let option_counts = [255; MAX_CHOICE_FIELDS];
+        let args = [
+            string(&title),
+            string(&description),
+            bytes(&fields),
+            uint(0),
+            bytes(&[4; 65]),
+            uint(POLICY_EDITABLE as u64),
+            choices(&option_counts),
+        ];
+        assert!(encode(&args).len() + 4 <= MAX_CALL_DATA_LEN);
+        let form_id: u64 = query("createForm(string,string,bytes,uint64,bytes,uint8,uint8[])", &args);
+        let out = call(
+            "updateForm(uint64,string,string,bytes,uint8[])",
+            &[uint(form_id), string(&title), string(&description), bytes(&fields), choices(&option_counts)],
+        );
+        assert!(!out.reverted);
+        let form: FormView = query("getForm(uint64)", &[uint(form_id)]);
+        assert_eq!(form.2, fields);
+
+        mock::set_caller(ALICE);
+        let picked = [254; MAX_CHOICE_FIELDS];
+        assert!(!submit(form_id, &[b'c'; 256], &picked).reverted);
+        assert!(!submit(form_id, &[b'c'; 256], &picked).reverted);
+
+        mock::set_caller(CREATOR);
+        let allowed: u64 = query("addToAllowlist(uint64,address[])", &[uint(form_id), allowlist(MAX_ALLOWLIST_BATCH)]);
+        assert_eq!(allowed, MAX_ALLOWLIST_BATCH as u64);
+        let removed: u64 = query("removeFromAllowlist(uint64,address[])", &[uint(form_id), allowlist(MAX_ALLOWLIST_BATCH)]);
+        assert_eq!(removed, MAX_ALLOWLIST_BATCH as u64);
+
+        // A proof filling the call data is decoded before it is rejected
+        let proof = Token::Array(vec![bytes32([1; 32]); (MAX_CALL_DATA_LEN - 4 - 8 * 32) / 32]);
+        let args = [uint(form_id), bytes(b"c"), choices(&[]), proof];
+        assert!(encode(&args).len() + 4 <= MAX_CALL_DATA_LEN);
+        assert!(call("submitResponseWithProof(uint64,bytes,uint8[],bytes32[])", &args).reverted);
+    }
+
+    fn extend(form_id: u64, new_deadline: u64) -> mock::Outcome {
+        call("extendDeadline(uint64,uint64)", &[uint(form_id), uint(new_deadline)])
+    }
+
+    fn update(form_id: u64, fields: &[u8], option_counts: &[u8]) -> mock::Outcome {
+        call(
+            "updateForm(uint64,string,string,bytes,uint8[])",
+            &[uint(form_id), string("New title"), string("New description"), bytes(fields), choices(option_counts)],
+        )
+    }
+
+    #[test]
+    fn extending_a_passed_deadline_reopens_the_form() {
+        setup();
+        let form_id = create_form(NOW + 10, POLICY_SINGLE, &[]);
+        mock::set_now(NOW + 11);
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"late", &[]).data, DeadlinePassed { form_id, deadline: NOW + 10 }.encode());
+
+        mock::set_caller(CREATOR);
+        mock::take_events();
+        assert!(!extend(form_id, NOW + 20).reverted);
+        assert_eq!(topics(), [DeadlineExtended::TOPIC]);
+        assert_eq!(query::<u64>("getFormDeadline(uint64)", &[uint(form_id)]), NOW + 20);
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"in time", &[]).reverted);
+    }
+
+    #[test]
+    fn deadlines_only_move_later() {
+        setup();
+        let form_id = create_form(NOW + 10, POLICY_SINGLE, &[]);
+        let invalid = |deadline, requested| InvalidDeadline { form_id, deadline, requested }.encode();
+        assert_eq!(extend(form_id, NOW + 5).data, invalid(NOW + 10, NOW + 5));
+        assert_eq!(extend(form_id, NOW + 10).data, invalid(NOW + 10, NOW + 10));
+        // An already-passed timestamp is still earlier than the deadline
+        assert_eq!(extend(form_id, NOW - 1).data, invalid(NOW + 10, NOW - 1));
+
+        // 0 removes the deadline, after which there is nothing to extend
+        assert!(!extend(form_id, 0).reverted);
+        assert_eq!(query::<u64>("getFormDeadline(uint64)", &[uint(form_id)]), 0);
+        assert_eq!(extend(form_id, NOW + 20).data, invalid(0, NOW + 20));
+        assert_eq!(extend(form_id, 0).data, invalid(0, 0));
+
+        let open_ended = create_form(0, POLICY_SINGLE, &[]);
+        assert_eq!(
+            extend(open_ended, NOW + 20).data,
+            InvalidDeadline { form_id: open_ended, deadline: 0, requested: NOW + 20 }.encode()
+        );
+    }
+
+    #[test]
+    fn closed_forms_keep_their_status_when_extended() {
+        setup();
+        let form_id = create_form(NOW + 10, POLICY_SINGLE, &[]);
+        mock::set_caller(ALICE);
+        assert_eq!(extend(form_id, NOW + 20).data, NotFormCreator { form_id, caller: ALICE }.encode());
+
+        mock::set_caller(CREATOR);
+        assert!(query::<bool>("closeForm(uint64)", &[uint(form_id)]));
+        assert!(!extend(form_id, NOW + 20).reverted);
+        assert_eq!(query::<u8>("getFormStatus(uint64)", &[uint(form_id)]), STATUS_CLOSED);
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"cid", &[]).data, FormIsClosed { form_id }.encode());
+
+        mock::set_caller(CREATOR);
+        assert!(query::<bool>("archiveForm(uint64)", &[uint(form_id)]));
+        assert_eq!(extend(form_id, NOW + 30).data, FormIsArchived { form_id }.encode());
+        assert_eq!(update(form_id, b"v2", &[]).data, FormIsArchived { form_id }.encode());
+        assert_eq!(extend(9, NOW + 30).data, FormNotFound { form_id: 9 }.encode());
+    }
+
+    #[test]
+    fn updates_keep_every_fields_version() {
+        setup();
+        let form_id = create_form(0, POLICY_EDITABLE, &[2, 3]);
+        let version = |form_id| query::<u64>("getFormSchemaVersion(uint64)", &[uint(form_id)]);
+        assert_eq!(version(form_id), 1);
+
+        // Unchanged fields keep the version
+        assert_eq!(update(form_id, b"[]", &[2, 3]).data, encode(&[uint(1)]));
+        assert_eq!(update(form_id, b"v2", &[2, 3]).data, encode(&[uint(2)]));
+        assert_eq!(query::<String>("getFormTitle(uint64)", &[uint(form_id)]), "New title");
+        assert_eq!(query::<Vec<u8>>("getFormFields(uint64)", &[uint(form_id)]), b"v2");
+        assert_eq!(query::<Vec<u8>>("getFormFields(uint64,uint64)", &[uint(form_id), uint(1)]), b"[]");
+        assert!(call("getFormFields(uint64,uint64)", &[uint(form_id), uint(3)]).reverted);
+
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"cid", &[1, 2]).reverted);
+        assert_eq!(query::<u64>("getResponseSchemaVersion(uint64,uint64)", &[uint(form_id), uint(0)]), 2);
+
+        // With responses, choice fields can only gain options
+        mock::set_caller(CREATOR);
+        let invalidates = SchemaChangeInvalidatesAggregates { form_id }.encode();
+        assert_eq!(update(form_id, b"v3", &[2]).data, invalidates);
+        assert_eq!(update(form_id, b"v3", &[1, 3]).data, invalidates);
+        assert_eq!(update(form_id, b"v3", &[2, 4]).data, encode(&[uint(3)]));
+        assert_eq!(aggregate(form_id, 1, 2), 1);
+
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"edited", &[0, 3]).reverted);
+        assert_eq!(query::<u64>("getResponseSchemaVersion(uint64,uint64)", &[uint(form_id), uint(0)]), 3);
+        assert_eq!((aggregate(form_id, 0, 1), aggregate(form_id, 1, 3)), (0, 1));
+    }
+
+    #[test]
+    fn deleted_forms_are_purged_in_batches() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[2, 3]);
+        assert!(!update(form_id, b"v2", &[2, 3]).reverted);
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"a1", &[1, 2]).reverted);
+        assert!(!submit(form_id, b"a2", &[1, 0]).reverted);
+        mock::set_caller(BOB);
+        assert!(!submit(form_id, b"b1", &[0, 2]).reverted);
+
+        let purge = |max_items: u64| call("purgeForm(uint64,uint64)", &[uint(form_id), uint(max_items)]);
+        mock::set_caller(CREATOR);
+        assert_eq!(purge(10).data, FormNotDeleted { form_id, status: STATUS_OPEN }.encode());
+        assert!(query::<bool>("closeForm(uint64)", &[uint(form_id)]));
+        assert!(query::<bool>("deleteForm(uint64)", &[uint(form_id)]));
+        assert_eq!(query::<u8>("getFormStatus(uint64)", &[uint(form_id)]), STATUS_DELETED);
+        assert_eq!(call("getForm(uint64)", &[uint(form_id)]).data, FormNotFound { form_id }.encode());
+        assert_eq!(purge(10).data.len(), 32);
+        mock::set_caller(ALICE);
+        assert_eq!(purge(10).data, NotFormCreator { form_id, caller: ALICE }.encode());
+        assert_eq!(call("purgeForm(uint64,uint64)", &[uint(9), uint(10)]).data, FormNotFound { form_id: 9 }.encode());
+    }
+
+    #[test]
+    fn purging_picks_up_where_the_last_batch_ended() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[2, 3]);
+        assert!(!update(form_id, b"v2", &[2, 3]).reverted);
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"a1", &[1, 2]).reverted);
+        assert!(!submit(form_id, b"a2", &[1, 0]).reverted);
+        mock::set_caller(CREATOR);
+        assert!(query::<bool>("closeForm(uint64)", &[uint(form_id)]));
+        assert!(query::<bool>("deleteForm(uint64)", &[uint(form_id)]));
+
+        // 2 responses, 2 + 3 tallies and 2 schema versions
+        let purge = |max_items: u64| query::<u64>("purgeForm(uint64,uint64)", &[uint(form_id), uint(max_items)]);
+        assert_eq!(purge(0), 9);
+        assert_eq!(purge(3), 6);
+        // The views revert for deleted forms, so read what's left directly
+        let tally = |field_idx: u8, option_idx: u8| AGGREGATES.get_or_default(&(form_id, field_idx, option_idx));
+        assert!(RESPONSE_CIDS.at(&form_id).is_empty());
+        assert_eq!((tally(1, 2), tally(1, 0)), (0, 1));
+        assert_eq!(purge(4), 2);
+        assert_eq!((tally(0, 0), tally(0, 1)), (0, 0));
+        assert_eq!(purge(4), 0);
+        assert_eq!(purge(4), 0);
+        assert!(!FORM_CHOICES.contains(&form_id));
+        assert!(FORM_FIELDS.at(&form_id).is_empty());
+        assert_eq!(query::<u8>("getFormStatus(uint64)", &[uint(form_id)]), STATUS_DELETED);
+    }
+
+    #[test]
+    fn empty_allowlists_leave_the_form_open() {
+        setup();
+        let form_id: u64 = query(
+            "createForm(string,string,bytes,uint64,bytes,uint8,uint8[],address[])",
+            &[
+                string("Title"),
+                string("Description"),
+                bytes(b"[]"),
+                uint(0),
+                bytes(&[4; 65]),
+                uint(POLICY_SINGLE as u64),
+                choices(&[]),
+                allowlist(0),
+            ],
+        );
+        let has_allowlist = || query::<bool>("hasAllowlist(uint64)", &[uint(form_id)]);
+        assert!(!has_allowlist());
+        assert_eq!(query::<u64>("addToAllowlist(uint64,address[])", &[uint(form_id), allowlist(0)]), 0);
+        assert!(!has_allowlist());
+        mock::set_caller(ALICE);
+        assert!(!submit(form_id, b"cid", &[]).reverted);
+
+        mock::set_caller(CREATOR);
+        let args = [uint(form_id), Token::Array(vec![address(ALICE)])];
+        assert_eq!(query::<u64>("addToAllowlist(uint64,address[])", &args), 1);
+        // Emptying a non-empty allowlist keeps the form restricted
+        assert_eq!(query::<u64>("removeFromAllowlist(uint64,address[])", &args), 1);
+        assert!(has_allowlist());
+        mock::set_caller(BOB);
+        assert_eq!(submit(form_id, b"cid", &[]).data, NotEligible { form_id, respondent: BOB }.encode());
+    }
+
+    #[test]
+    fn merkle_members_respond_with_a_proof() {
+        setup();
+        let form_id = create_form(0, POLICY_SINGLE, &[]);
+        // Two-leaf tree: each member's proof is the other's leaf
+        let (alice_leaf, carol_leaf) = (merkle::address_leaf(&ALICE), merkle::address_leaf(&CAROL));
+        let root = merkle::process_proof(&[carol_leaf], alice_leaf);
+        let set_root = |root: [u8; 32]| call("setMerkleRoot(uint64,bytes32)", &[uint(form_id), bytes32(root)]);
+        let proof = |leaf: [u8; 32]| Token::Array(vec![bytes32(leaf)]);
+        let submit_with_proof = |cid: &[u8], leaf| {
+            call("submitResponseWithProof(uint64,bytes,uint8[],bytes32[])", &[uint(form_id), bytes(cid), choices(&[]), proof(leaf)])
+        };
+
+        mock::set_caller(ALICE);
+        assert_eq!(set_root(root).data, NotFormCreator { form_id, caller: ALICE }.encode());
+        mock::set_caller(CREATOR);
+        assert!(!set_root(root).reverted);
+        assert_eq!(query::<[u8; 32]>("getMerkleRoot(uint64)", &[uint(form_id)]), root);
+        let eligible = |respondent, leaf| {
+            query::<bool>("isEligible(uint64,address,bytes32[])", &[uint(form_id), address(respondent), proof(leaf)])
+        };
+        assert!(eligible(ALICE, carol_leaf) && eligible(CAROL, alice_leaf));
+        assert!(!eligible(BOB, carol_leaf) && !eligible(ALICE, alice_leaf));
+
+        // Members need their proof; a borrowed one doesn't help
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"cid", &[]).data, NotEligible { form_id, respondent: ALICE }.encode());
+        assert_eq!(submit_with_proof(b"alice", alice_leaf).data, NotEligible { form_id, respondent: ALICE }.encode());
+        assert_eq!(submit_with_proof(b"alice", carol_leaf).data, encode(&[uint(0)]));
+        mock::set_caller(BOB);
+        assert_eq!(submit_with_proof(b"bob", carol_leaf).data, NotEligible { form_id, respondent: BOB }.encode());
+
+        // The allowlist admits respondents alongside the tree
+        mock::set_caller(CREATOR);
+        assert!(!call("addToAllowlist(uint64,address[])", &[uint(form_id), Token::Array(vec![address(BOB)])]).reverted);
+        mock::set_caller(BOB);
+        assert_eq!(submit(form_id, b"bob", &[]).data, encode(&[uint(1)]));
+
+        // A zero root removes the tree, leaving only the allowlist
+        mock::set_caller(CREATOR);
+        assert!(!set_root([0; 32]).reverted);
+        assert_eq!(query::<[u8; 32]>("getMerkleRoot(uint64)", &[uint(form_id)]), [0; 32]);
+        mock::set_caller(CAROL);
+        assert_eq!(submit_with_proof(b"carol", alice_leaf).data, NotEligible { form_id, respondent: CAROL }.encode());
+    }
+
+    const ACCESS_PASS: [u8; 20] = [0xaa; 20];
+
+    /// Stand-in AccessPass holding `passes` as `(holder, locationId,
+    /// accessLevel, valid)`, indexed from pass ID 1; unknown IDs revert
+    fn register_access_pass(passes: &'static [([u8; 20], &'static str, &'static str, bool)]) {
+        mock::register_contract(ACCESS_PASS, |input| {
+            let pass_id = decode(&[ethabi::ParamType::Uint(256)], &input[4..]).unwrap().remove(0).into_uint().unwrap();
+            let Some(&(holder, location_id, access_level, valid)) = passes.get(pass_id.as_usize().wrapping_sub(1)) else {
+                return mock::Outcome { reverted: true, data: errors::error_string("Pass does not exist") };
+            };
+            let data = if input[..4] == GET_PASS_METADATA {
+                encode(&[Token::Tuple(vec![
+                    string("Office"),
+                    string(location_id),
+                    address(holder),
+                    uint(NOW),
+                    uint(0),
+                    string(access_level),
+                    Token::Bool(!valid),
+                    string(""),
+                ])])
+            } else {
+                encode(&[Token::Bool(valid)])
+            };
+            mock::Outcome { reverted: false, data }
+        });
+    }
+
+    fn set_pass_gate(form_id: u64, access_pass: [u8; 20], location_id: &str, access_level: &str) -> mock::Outcome {
+        call(
+            "setPassGate(uint64,address,string,string)",
+            &[uint(form_id), address(access_pass), string(location_id), string(access_level)],
+        )
+    }
+
+    fn submit_with_pass(form_id: u64, cid: &[u8], pass_id: u64) -> mock::Outcome {
+        call(
+            "submitResponseWithPass(uint64,bytes,uint8[],bytes32[],uint256)",
+            &[uint(form_id), bytes(cid), choices(&[]), Token::Array(Vec::new()), uint(pass_id)],
+        )
+    }
+
+    #[test]
+    fn pass_gated_forms_check_the_pass() {
+        setup();
+        register_access_pass(&[
+            (ALICE, "hq", "staff", true),
+            (ALICE, "lab", "staff", true),
+            (ALICE, "hq", "visitor", true),
+            (BOB, "hq", "staff", false),
+        ]);
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+        mock::set_caller(ALICE);
+        assert_eq!(set_pass_gate(form_id, ACCESS_PASS, "hq", "staff").data, NotFormCreator { form_id, caller: ALICE }.encode());
+        mock::set_caller(CREATOR);
+        assert!(!set_pass_gate(form_id, ACCESS_PASS, "hq", "staff").reverted);
+        let gate: ([u8; 20], String, String) = query("getPassGate(uint64)", &[uint(form_id)]);
+        assert_eq!(gate, (ACCESS_PASS, "hq".into(), "staff".into()));
+
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"cid", &[]).data, PassRequired { form_id }.encode());
+        assert!(!query::<bool>("isEligible(uint64,address)", &[uint(form_id), address(ALICE)]));
+        let invalid = |pass_id: u64| InvalidPass { form_id, pass_id: pass_id.into() }.encode();
+        // Wrong location, wrong level, someone else's pass, unknown pass
+        for pass_id in [2, 3, 4, 9] {
+            assert_eq!(submit_with_pass(form_id, b"cid", pass_id).data, invalid(pass_id));
+        }
+        assert_eq!(submit_with_pass(form_id, b"cid", 1).data, encode(&[uint(0)]));
+        let eligible = |respondent, pass_id| {
+            query::<bool>(
+                "isEligible(uint64,address,bytes32[],uint256)",
+                &[uint(form_id), address(respondent), Token::Array(Vec::new()), uint(pass_id)],
+            )
+        };
+        assert!(eligible(ALICE, 1) && !eligible(BOB, 1));
+        // A revoked or expired pass is rejected even by its holder
+        mock::set_caller(BOB);
+        assert_eq!(submit_with_pass(form_id, b"cid", 4).data, invalid(4));
+        assert!(!eligible(BOB, 4));
+
+        // Empty location and level match any pass; the zero address removes the gate
+        mock::set_caller(CREATOR);
+        assert!(!set_pass_gate(form_id, ACCESS_PASS, "", "").reverted);
+        mock::set_caller(ALICE);
+        assert!(!submit_with_pass(form_id, b"cid", 2).reverted);
+        assert!(!submit_with_pass(form_id, b"cid", 3).reverted);
+        mock::set_caller(CREATOR);
+        assert!(!set_pass_gate(form_id, [0; 20], "", "").reverted);
+        assert_eq!(query::<([u8; 20], String, String)>("getPassGate(uint64)", &[uint(form_id)]).0, [0; 20]);
+        mock::set_caller(BOB);
+        assert!(!submit(form_id, b"cid", &[]).reverted);
+    }
+
+    #[test]
+    fn pass_contracts_that_misbehave_grant_no_pass() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+        assert!(!set_pass_gate(form_id, ACCESS_PASS, "", "").reverted);
+        let invalid = InvalidPass { form_id, pass_id: 1.into() }.encode();
+        mock::set_caller(ALICE);
+
+        // No code at the address: calls succeed with empty output
+        assert_eq!(submit_with_pass(form_id, b"cid", 1).data, invalid);
+        // Malformed return data
+        mock::register_contract(ACCESS_PASS, |_| mock::Outcome { reverted: false, data: vec![1; 40] });
+        assert_eq!(submit_with_pass(form_id, b"cid", 1).data, invalid);
+    }
+
+    /// Stand-in personhood precompile: `statuses` lists everyone with a
+    /// status in the "dotns" context, each with a context alias derived from
+    /// their address
+    fn register_personhood(statuses: &'static [([u8; 20], u8)]) {
+        mock::register_contract(personhood::PRECOMPILE, |input| {
+            let params = [ethabi::ParamType::Address, ethabi::ParamType::FixedBytes(32)];
+            let mut args = decode(&params, &input[4..]).unwrap();
+            let context = args.pop().unwrap().into_fixed_bytes().unwrap();
+            let account: [u8; 20] = args.pop().unwrap().into_address().unwrap().0;
+            let status = match statuses.iter().find(|(holder, _)| *holder == account) {
+                Some(&(_, status)) if context == personhood::DOTNS_CONTEXT => status,
+                _ => personhood::STATUS_NONE,
+            };
+            let alias = keccak::hash(&[account.as_slice(), &context].concat());
+            let data = encode(&[uint(status as u64), bytes32(alias)]);
+            mock::Outcome { reverted: false, data }
+        });
+    }
+
+    fn require_personhood(form_id: u64, min_status: u8) -> mock::Outcome {
+        call("setPersonhoodRequirement(uint64,uint8)", &[uint(form_id), uint(min_status as u64)])
+    }
+
+    #[test]
+    fn personhood_requirements_gate_respondents() {
+        setup();
+        register_personhood(&[(ALICE, personhood::STATUS_FULL), (BOB, personhood::STATUS_LITE)]);
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+        mock::set_caller(ALICE);
+        assert_eq!(require_personhood(form_id, 1).data, NotFormCreator { form_id, caller: ALICE }.encode());
+        mock::set_caller(CREATOR);
+        assert_eq!(require_personhood(form_id, 3).data, InvalidPersonhoodStatus { status: 3 }.encode());
+        assert!(!require_personhood(form_id, personhood::STATUS_LITE).reverted);
+        assert_eq!(query::<u8>("getPersonhoodRequirement(uint64)", &[uint(form_id)]), personhood::STATUS_LITE);
+
+        let required = |respondent, required, status| PersonhoodRequired { form_id, respondent, required, status }.encode();
+        let eligible = |respondent| query::<bool>("isEligible(uint64,address)", &[uint(form_id), address(respondent)]);
+        for respondent in [ALICE, BOB] {
+            mock::set_caller(respondent);
+            assert!(!submit(form_id, b"cid", &[]).reverted);
+        }
+        mock::set_caller(CAROL);
+        assert_eq!(submit(form_id, b"cid", &[]).data, required(CAROL, 1, 0));
+        assert!(!eligible(CAROL));
+
+        mock::set_caller(CREATOR);
+        assert!(!require_personhood(form_id, personhood::STATUS_FULL).reverted);
+        mock::set_caller(BOB);
+        assert_eq!(submit(form_id, b"cid", &[]).data, required(BOB, 2, 1));
+        assert!(eligible(ALICE) && !eligible(BOB));
+
+        // 0 removes the requirement
+        mock::set_caller(CREATOR);
+        assert!(!require_personhood(form_id, personhood::STATUS_NONE).reverted);
+        assert_eq!(query::<u8>("getPersonhoodRequirement(uint64)", &[uint(form_id)]), 0);
+        mock::set_caller(CAROL);
+        assert!(!submit(form_id, b"cid", &[]).reverted);
+    }
+
+    #[test]
+    fn an_unreachable_precompile_counts_as_no_personhood() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+        assert!(!require_personhood(form_id, personhood::STATUS_LITE).reverted);
+        mock::set_caller(ALICE);
+        let required = PersonhoodRequired { form_id, respondent: ALICE, required: 1, status: 0 }.encode();
+
+        // No precompile: the call succeeds with empty output
+        assert_eq!(submit(form_id, b"cid", &[]).data, required);
+        mock::register_contract(personhood::PRECOMPILE, |_| mock::Outcome { reverted: true, data: Vec::new() });
+        assert_eq!(submit(form_id, b"cid", &[]).data, required);
+    }
+
+    /// Context alias `register_personhood` reports for `account`
+    fn context_alias(account: [u8; 20]) -> [u8; 32] {
+        keccak::hash(&[account.as_slice(), &personhood::DOTNS_CONTEXT].concat())
+    }
+
+    fn submit_pseudonymous(form_id: u64, nullifier: [u8; 32], picked: &[u8]) -> mock::Outcome {
+        call(
+            "submitPseudonymousResponse(uint64,bytes32,bytes,uint8[])",
+            &[uint(form_id), bytes32(nullifier), bytes(b"cid"), choices(picked)],
+        )
+    }
+
+    fn set_pseudonymous(form_id: u64, pseudonymous: bool) -> mock::Outcome {
+        call("setPseudonymous(uint64,bool)", &[uint(form_id), Token::Bool(pseudonymous)])
+    }
+
+    #[test]
+    fn pseudonymous_forms_require_personhood() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+        assert_eq!(set_pseudonymous(form_id, true).data, PseudonymityRequiresPersonhood { form_id }.encode());
+        assert!(!require_personhood(form_id, personhood::STATUS_LITE).reverted);
+        assert!(!set_pseudonymous(form_id, true).reverted);
+        assert!(query::<bool>("isPseudonymous(uint64)", &[uint(form_id)]));
+
+        // The requirement can be raised but not dropped
+        assert_eq!(require_personhood(form_id, personhood::STATUS_NONE).data, PseudonymityRequiresPersonhood { form_id }.encode());
+        assert!(!require_personhood(form_id, personhood::STATUS_FULL).reverted);
+        assert!(!set_pseudonymous(form_id, false).reverted);
+        assert!(!require_personhood(form_id, personhood::STATUS_NONE).reverted);
+    }
+
+    #[test]
+    fn pseudonymous_responses_take_one_nullifier_per_person() {
+        setup();
+        register_personhood(&[(ALICE, personhood::STATUS_FULL), (BOB, personhood::STATUS_LITE)]);
+        let form_id = create_form(0, POLICY_UNLIMITED, &[2]);
+        let other_form = create_form(0, POLICY_UNLIMITED, &[2]);
+        for form_id in [form_id, other_form] {
+            assert!(!require_personhood(form_id, personhood::STATUS_LITE).reverted);
+            assert!(!set_pseudonymous(form_id, true).reverted);
+        }
+        let nullifier = |account, form_id: u64| {
+            query::<[u8; 32]>("computeNullifier(bytes32,uint64)", &[bytes32(context_alias(account)), uint(form_id)])
+        };
+        let mut preimage = context_alias(ALICE).to_vec();
+        preimage.extend(form_id.to_be_bytes());
+        assert_eq!(nullifier(ALICE, form_id), keccak::hash(&preimage));
+        assert_ne!(nullifier(ALICE, form_id), nullifier(ALICE, other_form));
+        mock::take_events();
+
+        // Only the caller's own nullifier for this form is accepted
+        mock::set_caller(ALICE);
+        let invalid = InvalidNullifier { form_id }.encode();
+        assert_eq!(submit_pseudonymous(form_id, [7; 32], &[1]).data, invalid);
+        assert_eq!(submit_pseudonymous(form_id, nullifier(BOB, form_id), &[1]).data, invalid);
+        assert_eq!(submit_pseudonymous(form_id, nullifier(ALICE, other_form), &[1]).data, invalid);
+        let own = nullifier(ALICE, form_id);
+        assert_eq!(submit_pseudonymous(form_id, own, &[1]).data, encode(&[uint(0)]));
+        assert!(query::<bool>("isNullifierSpent(uint64,bytes32)", &[uint(form_id), bytes32(own)]));
+        assert_eq!(topics(), [PseudonymousResponseSubmitted::TOPIC]);
+        assert!(!query::<bool>("hasSubmitted(uint64,address)", &[uint(form_id), address(ALICE)]));
+
+        // Even on an unlimited form a person responds once
+        assert_eq!(submit_pseudonymous(form_id, own, &[0]).data, NullifierSpent { form_id, nullifier: own }.encode());
+        assert_eq!(submit(form_id, b"cid", &[0]).data, WrongSubmissionMode { form_id, pseudonymous: true }.encode());
+        assert!(!submit_pseudonymous(other_form, nullifier(ALICE, other_form), &[0]).reverted);
+
+        mock::set_caller(BOB);
+        assert_eq!(submit_pseudonymous(form_id, nullifier(BOB, form_id), &[0]).data, encode(&[uint(1)]));
+        mock::set_caller(CAROL);
+        let required = PersonhoodRequired { form_id, respondent: CAROL, required: 1, status: 0 }.encode();
+        assert_eq!(submit_pseudonymous(form_id, nullifier(CAROL, form_id), &[0]).data, required);
+        assert_eq!((aggregate(form_id, 0, 0), aggregate(form_id, 0, 1)), (1, 1));
+    }
+
+    fn set_commit_reveal(form_id: u64, reveal_deadline: u64) -> mock::Outcome {
+        call("setCommitReveal(uint64,uint64)", &[uint(form_id), uint(reveal_deadline)])
+    }
+
+    fn commitment(picked: &[u8], salt: u8) -> [u8; 32] {
+        query("computeCommitment(uint8[],bytes32)", &[choices(picked), bytes32([salt; 32])])
+    }
+
+    fn commit(form_id: u64, commitment: [u8; 32]) -> mock::Outcome {
+        call("commitResponse(uint64,bytes32,bytes)", &[uint(form_id), bytes32(commitment), bytes(b"cid")])
+    }
+
+    fn reveal(form_id: u64, picked: &[u8], salt: u8) -> mock::Outcome {
+        call("revealResponse(uint64,uint8[],bytes32)", &[uint(form_id), choices(picked), bytes32([salt; 32])])
+    }
+
+    fn unrevealed(form_id: u64) -> u64 {
+        query("getUnrevealedCount(uint64)", &[uint(form_id)])
+    }
+
+    fn commitment_of_respondent(form_id: u64, respondent: [u8; 20]) -> [u8; 32] {
+        query("getCommitment(uint64,address)", &[uint(form_id), address(respondent)])
+    }
+
+    #[test]
+    fn commit_reveal_needs_a_later_reveal_deadline() {
+        setup();
+        let open_ended = create_form(0, POLICY_SINGLE, &[2]);
+        assert_eq!(set_commit_reveal(open_ended, NOW).data, InvalidDeadline { form_id: open_ended, deadline: 0, requested: NOW }.encode());
+        let unlimited = create_form(NOW + 100, POLICY_UNLIMITED, &[2]);
+        assert_eq!(set_commit_reveal(unlimited, NOW + 200).data, InvalidSubmissionPolicy { policy: POLICY_UNLIMITED }.encode());
+
+        let form_id = create_form(NOW + 100, POLICY_SINGLE, &[2]);
+        let too_early = InvalidDeadline { form_id, deadline: NOW + 100, requested: NOW + 100 }.encode();
+        assert_eq!(set_commit_reveal(form_id, NOW + 100).data, too_early);
+        assert!(!set_commit_reveal(form_id, NOW + 200).reverted);
+        assert_eq!(query::<u64>("getRevealDeadline(uint64)", &[uint(form_id)]), NOW + 200);
+
+        // Commit-reveal forms take commitments only, and other forms none
+        mock::set_caller(ALICE);
+        assert_eq!(submit(form_id, b"cid", &[1]).data, WrongCommitRevealMode { form_id, commit_reveal: true }.encode());
+        let other = WrongCommitRevealMode { form_id: unlimited, commit_reveal: false }.encode();
+        assert_eq!(commit(unlimited, commitment(&[1], 1)).data, other);
+        assert!(!commit(form_id, commitment(&[1], 1)).reverted);
+        mock::set_caller(CREATOR);
+        assert_eq!(set_commit_reveal(form_id, 0).data, FormHasResponses { form_id }.encode());
+
+        let not_found = FormNotFound { form_id: 9 }.encode();
+        assert_eq!(call("getUnrevealedCount(uint64)", &[uint(9)]).data, not_found);
+        assert_eq!(call("getCommitment(uint64,address)", &[uint(9), address(ALICE)]).data, not_found);
+    }
+
+    #[test]
+    fn commitments_are_revealed_between_the_deadlines() {
+        setup();
+        let form_id = create_form(NOW + 100, POLICY_EDITABLE, &[3]);
+        assert!(!set_commit_reveal(form_id, NOW + 200).reverted);
+
+        // Editable forms take a replacement commitment until the deadline
+        mock::set_caller(ALICE);
+        assert_eq!(commit(form_id, commitment(&[0], 1)).data, encode(&[uint(0)]));
+        mock::set_now(NOW + 100);
+        assert_eq!(commit(form_id, commitment(&[2], 2)).data, encode(&[uint(0)]));
+        assert_eq!(commitment_of_respondent(form_id, ALICE), commitment(&[2], 2));
+        let outside_reveal_phase = NotInRevealPhase { form_id, deadline: NOW + 100, reveal_deadline: NOW + 200 }.encode();
+        assert_eq!(reveal(form_id, &[2], 2).data, outside_reveal_phase);
+        for respondent in [BOB, CAROL] {
+            mock::set_caller(respondent);
+            assert!(!commit(form_id, commitment(&[1], 3)).reverted);
+        }
+        assert_eq!((unrevealed(form_id), response_count(form_id)), (3, 3));
+
+        mock::set_now(NOW + 101);
+        assert_eq!(commit(form_id, commitment(&[1], 4)).data, DeadlinePassed { form_id, deadline: NOW + 100 }.encode());
+        mock::set_caller(ALICE);
+        assert_eq!(reveal(form_id, &[0], 1).data, CommitmentMismatch { form_id, respondent: ALICE }.encode());
+        assert!(query::<bool>("revealResponse(uint64,uint8[],bytes32)", &[uint(form_id), choices(&[2]), bytes32([2; 32])]));
+        assert_eq!(reveal(form_id, &[2], 2).data, NothingToReveal { form_id, respondent: ALICE }.encode());
+        assert_eq!(commitment_of_respondent(form_id, ALICE), [0; 32]);
+        assert_eq!((unrevealed(form_id), aggregate(form_id, 0, 2)), (2, 1));
+
+        // Closing the form doesn't stop reveals
+        mock::set_caller(CREATOR);
+        assert!(query::<bool>("closeForm(uint64)", &[uint(form_id)]));
+        mock::set_caller(BOB);
+        mock::set_now(NOW + 200);
+        assert!(!reveal(form_id, &[1], 3).reverted);
+
+        // Commitments not revealed in time are never tallied
+        mock::set_caller(CAROL);
+        mock::set_now(NOW + 201);
+        assert_eq!(reveal(form_id, &[1], 3).data, outside_reveal_phase);
+        assert_eq!((unrevealed(form_id), aggregate(form_id, 0, 1)), (1, 1));
+        assert_eq!(commitment_of_respondent(form_id, CAROL), commitment(&[1], 3));
+    }
+
+    #[test]
+    fn over_long_cids_and_keys_revert() {
+        setup();
+        let create = |pubkey: &[u8]| {
+            call("createForm(string,string,bytes,uint64,bytes)", &[string("t"), string("d"), bytes(b"[]"), uint(0), bytes(pubkey)])
+        };
+        assert_eq!(create(&[4; MAX_PUBKEY_LEN + 1]).data, too_long("encryptionPubKey", MAX_PUBKEY_LEN + 1, MAX_PUBKEY_LEN));
+        assert_eq!(create(&[4; MAX_PUBKEY_LEN]).data, encode(&[uint(1)]));
+        assert_eq!(query::<Vec<u8>>("getFormPubKey(uint64)", &[uint(1)]), [4; MAX_PUBKEY_LEN]);
+
+        let cid = [b'c'; MAX_CID_LEN + 1];
+        let rejected = too_long("cid", MAX_CID_LEN + 1, MAX_CID_LEN);
+        mock::set_caller(ALICE);
+        assert_eq!(submit(1, &cid, &[]).data, rejected);
+        assert_eq!(submit(1, &cid[..MAX_CID_LEN], &[]).data, encode(&[uint(0)]));
+
+        mock::set_caller(CREATOR);
+        let committed = create_form(NOW + 100, POLICY_SINGLE, &[2]);
+        assert!(!set_commit_reveal(committed, NOW + 200).reverted);
+        register_personhood(&[(ALICE, personhood::STATUS_FULL)]);
+        let pseudonymous = create_form(0, POLICY_SINGLE, &[2]);
+        assert!(!require_personhood(pseudonymous, personhood::STATUS_LITE).reverted);
+        assert!(!set_pseudonymous(pseudonymous, true).reverted);
+
+        mock::set_caller(ALICE);
+        let out = call("commitResponse(uint64,bytes32,bytes)", &[uint(committed), bytes32(commitment(&[1], 3)), bytes(&cid)]);
+        assert_eq!(out.data, rejected);
+        let nullifier = query::<[u8; 32]>("computeNullifier(bytes32,uint64)", &[bytes32(context_alias(ALICE)), uint(pseudonymous)]);
+        let out = call(
+            "submitPseudonymousResponse(uint64,bytes32,bytes,uint8[])",
+            &[uint(pseudonymous), bytes32(nullifier), bytes(&cid), choices(&[1])],
+        );
+        assert_eq!(out.data, rejected);
+        assert_eq!((response_count(committed), response_count(pseudonymous)), (0, 0));
+    }
+
+    #[test]
+    fn response_pages_stop_at_the_byte_budget() {
+        setup();
+        let form_id = create_form(0, POLICY_UNLIMITED, &[]);
+        mock::set_caller(ALICE);
+        for _ in 0..30 {
+            assert!(!submit(form_id, &[b'c'; MAX_CID_LEN], &[]).reverted);
+        }
+        type Page = Vec<(u64, [u8; 20], u64, Vec<u8>)>;
+        let page = |offset: u64, limit: u64| query::<Page>("getResponses(uint64,uint64,uint64)", &[uint(form_id), uint(offset), uint(limit)]);
+
+        // Each entry takes 6 * 32 bytes of head and length words plus its CID
+        let full = page(0, 30);
+        mock::assert_full_page(full.len(), 6 * 32 + MAX_CID_LEN);
+        assert_eq!(full[17], (17, ALICE, NOW, vec![b'c'; MAX_CID_LEN]));
+        assert_eq!(page(18, 30).len(), 12);
+        assert_eq!(page(0, 5).len(), 5);
+        assert_eq!(call("getResponses(uint64,uint64,uint64)", &[uint(9), uint(0), uint(1)]).data, FormNotFound { form_id: 9 }.encode());
+    }
 }
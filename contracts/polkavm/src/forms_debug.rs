@@ -1,12 +1,17 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
 use alloc::vec::Vec;
 
+#[cfg(not(feature = "mock"))]
 use polkavm_derive::polkavm_export;
-use uapi::{HostFn, HostFnImpl as api, ReturnFlags, StorageFlags};
+use intran3t_polkavm_contracts::errors;
+use intran3t_polkavm_contracts::storage::{get_storage, set_storage};
+#[cfg(not(feature = "mock"))]
+use uapi::HostFn;
+use intran3t_polkavm_contracts::host::{Api as api, ReturnFlags};
 
+#[cfg(not(feature = "mock"))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     unsafe {
@@ -15,6 +20,7 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
     }
 }
 
+#[cfg(not(feature = "mock"))]
 #[global_allocator]
 static ALLOCATOR: simplealloc::SimpleAlloc<4096> = simplealloc::SimpleAlloc::new();
 
@@ -27,28 +33,18 @@ fn storage_key() -> [u8; 32] {
 }
 
 fn store_bytes(data: &[u8]) {
-    let key = storage_key();
-    api::set_storage(StorageFlags::empty(), &key, data);
+    set_storage(&storage_key(), data);
 }
 
 fn read_bytes() -> Vec<u8> {
-    let key = storage_key();
-    let mut buf = [0u8; 128];
-    match api::get_storage(StorageFlags::empty(), &key, &mut &mut buf[..]) {
-        Ok(_) => {
-            let len = buf.iter().rposition(|&b| b != 0).map_or(0, |p| p + 1);
-            buf[..len].to_vec()
-        }
-        Err(_) => Vec::new(),
-    }
+    get_storage(&storage_key()).unwrap_or_default()
 }
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn call() {
     let length = api::call_data_size() as usize;
     if length < 4 {
-        api::return_value(ReturnFlags::REVERT, b"Too short");
-        return;
+        errors::revert("Too short");
     }
 
     let mut selector = [0u8; 4];
@@ -56,8 +52,7 @@ pub extern "C" fn call() {
 
     let data_len = length.saturating_sub(4).min(256);
     let data = if data_len > 0 {
-        let mut buf = Vec::with_capacity(data_len);
-        buf.resize(data_len, 0);
+        let mut buf = alloc::vec![0u8; data_len];
         api::call_data_copy(&mut buf, 4);
         buf
     } else {
@@ -76,10 +71,13 @@ pub extern "C" fn call() {
             api::return_value(ReturnFlags::empty(), &stored);
         }
         _ => {
-            api::return_value(ReturnFlags::REVERT, b"Unknown");
+            errors::revert("Unknown selector");
         }
     }
 }
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn deploy() {}
+
+#[cfg(feature = "mock")]
+fn main() {}
@@ -1,12 +1,17 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
 use alloc::vec::Vec;
 
+#[cfg(not(feature = "mock"))]
 use polkavm_derive::polkavm_export;
-use uapi::{HostFn, HostFnImpl as api, ReturnFlags, StorageFlags};
+use intran3t_polkavm_contracts::errors;
+use intran3t_polkavm_contracts::storage::{get_storage, set_storage};
+#[cfg(not(feature = "mock"))]
+use uapi::HostFn;
+use intran3t_polkavm_contracts::host::{Api as api, ReturnFlags};
 
+#[cfg(not(feature = "mock"))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     unsafe {
@@ -15,31 +20,24 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
     }
 }
 
+#[cfg(not(feature = "mock"))]
 #[global_allocator]
 static ALLOCATOR: simplealloc::SimpleAlloc<4096> = simplealloc::SimpleAlloc::new();
 
 // Test ABI parsing - same logic as forms_v2
 const SEL_STORE: [u8; 4] = [0x18, 0xf8, 0x34, 0x1b]; // storeAbiBytes(bytes)
 const SEL_READ: [u8; 4] = [0x6e, 0x23, 0x28, 0xfc];  // readAbiBytes()
-const SEL_CHECK_LEN: [u8; 4] = [0xDD, 0xEE, 0xFF, 0x00]; // checkCidLength(bytes) - returns uint256
 
 fn storage_key() -> [u8; 32] {
     [0u8; 32]
 }
 
 fn store_bytes(data: &[u8]) {
-    api::set_storage(StorageFlags::empty(), &storage_key(), data);
+    set_storage(&storage_key(), data);
 }
 
 fn read_bytes() -> Vec<u8> {
-    let mut buf = [0u8; 128];
-    match api::get_storage(StorageFlags::empty(), &storage_key(), &mut &mut buf[..]) {
-        Ok(_) => {
-            let len = buf.iter().rposition(|&b| b != 0).map_or(0, |p| p + 1);
-            buf[..len].to_vec()
-        }
-        Err(_) => Vec::new(),
-    }
+    get_storage(&storage_key()).unwrap_or_default()
 }
 
 /// Read ABI-encoded `bytes` parameter - EXACT SAME as forms_v2
@@ -71,7 +69,7 @@ fn parse_abi_bytes(data: &[u8], word_idx: usize) -> Vec<u8> {
 
 /// Encode `bytes` return value - EXACT SAME as forms_v2
 fn encode_abi_bytes(data: &[u8]) -> Vec<u8> {
-    let padded_len = ((data.len() + 31) / 32) * 32;
+    let padded_len = data.len().div_ceil(32) * 32;
     let mut out = alloc::vec![0u8; 64 + padded_len];
     // offset = 0x20
     out[31] = 0x20;
@@ -83,12 +81,11 @@ fn encode_abi_bytes(data: &[u8]) -> Vec<u8> {
     out
 }
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn call() {
     let length = api::call_data_size() as usize;
     if length < 4 {
-        api::return_value(ReturnFlags::REVERT, b"Too short");
-        return;
+        errors::revert("Too short");
     }
 
     let mut selector = [0u8; 4];
@@ -116,10 +113,13 @@ pub extern "C" fn call() {
             api::return_value(ReturnFlags::empty(), &encode_abi_bytes(&stored));
         }
         _ => {
-            api::return_value(ReturnFlags::REVERT, b"Unknown");
+            errors::revert("Unknown selector");
         }
     }
 }
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn deploy() {}
+
+#[cfg(feature = "mock")]
+fn main() {}
@@ -1,15 +1,22 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
+use ethabi::Uint as U256;
 
+#[cfg(not(feature = "mock"))]
 use polkavm_derive::polkavm_export;
-use uapi::{HostFn, HostFnImpl as api, ReturnFlags, StorageFlags};
+#[cfg(not(feature = "mock"))]
+use uapi::HostFn;
+use intran3t_polkavm_contracts::abi::{DispatchError, MAX_PAGE_BYTES};
+use intran3t_polkavm_contracts::host::{Api as api, ReturnFlags};
+use intran3t_polkavm_contracts::{dispatch, errors, sol_event, storage_layout};
 
 // ============ Runtime support ============
 
+#[cfg(not(feature = "mock"))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     unsafe {
@@ -18,266 +25,479 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
     }
 }
 
+/// An allowlist batch at the call data bound needs about 47 KB
+#[cfg_attr(feature = "mock", allow(dead_code))]
+const HEAP_SIZE: usize = 64 * 1024;
+
+#[cfg(not(feature = "mock"))]
 #[global_allocator]
-static ALLOCATOR: simplealloc::SimpleAlloc<4096> = simplealloc::SimpleAlloc::new();
+static ALLOCATOR: simplealloc::SimpleAlloc<HEAP_SIZE> = simplealloc::SimpleAlloc::new();
+
+// Call data is copied to the heap whole
+const MAX_CALL_DATA_LEN: usize = 4 * 1024;
+
+// ============ Storage layout ============
+
+storage_layout! {
+    FORM_COUNT: StorageValue<U256> = 0x01;
+    FORM_CIDS: StorageMap<U256, String> = 0x02;
+    FORM_CREATORS: StorageMap<U256, [u8; 20]> = 0x03;
+    RESPONSE_CIDS: StorageVecMap<U256, String> = 0x04;
+    ALLOWLIST: StorageMap<(U256, [u8; 20]), bool> = 0x05;
+    ALLOWLIST_ENABLED: StorageMap<U256, bool> = 0x06;
+    RESPONSE_RESPONDENTS: StorageMap<(U256, u64), [u8; 20]> = 0x07;
+    RESPONSE_TIMESTAMPS: StorageMap<(U256, u64), U256> = 0x08;
+}
 
-// ============ Function selectors (keccak256) ============
+// ============ Events (match FormsV2.sol) ============
 
-const SEL_REGISTER_FORM: [u8; 4] = [0x46, 0xab, 0x8f, 0x3c]; // registerForm(bytes)
-const SEL_SUBMIT_RESPONSE: [u8; 4] = [0xae, 0x57, 0xb6, 0x3d]; // submitResponse(uint64,bytes)
-const SEL_GET_FORM_CID: [u8; 4] = [0x3e, 0xc6, 0xf3, 0x04]; // getFormCid(uint64)
-const SEL_GET_RESPONSE_CID: [u8; 4] = [0xa1, 0xde, 0x25, 0x51]; // getResponseCid(uint64,uint64)
-const SEL_FORM_COUNT: [u8; 4] = [0x14, 0x6c, 0x14, 0x15]; // formCount()
-const SEL_RESPONSE_COUNT: [u8; 4] = [0xb3, 0x78, 0xe2, 0xc2]; // responseCount(uint64)
+sol_event! {
+    #[signature("FormRegistered(uint256,string,address,uint256)")]
+    struct FormRegistered {
+        #[indexed] form_id: U256,
+        cid: String,
+        #[indexed] creator: [u8; 20],
+        timestamp: U256,
+    }
+}
 
-// ============ Storage namespaces ============
+sol_event! {
+    #[signature("ResponseSubmitted(uint256,uint256,string,uint256)")]
+    struct ResponseSubmitted {
+        #[indexed] form_id: U256,
+        response_idx: U256,
+        cid: String,
+        timestamp: U256,
+    }
+}
 
-const NS_FORM_COUNTER: u8 = 0x01;
-const NS_FORM_CID: u8 = 0x02;
-const NS_FORM_CREATOR: u8 = 0x03;
-const NS_RESPONSE_COUNTER: u8 = 0x04;
-const NS_RESPONSE_CID: u8 = 0x05;
+sol_event! {
+    #[signature("AllowlistUpdated(uint256,address,bool)")]
+    struct AllowlistUpdated {
+        #[indexed] form_id: U256,
+        #[indexed] respondent: [u8; 20],
+        allowed: bool,
+    }
+}
 
-// ============ Storage key builders ============
+// ============ Environment ============
 
-fn key_form_counter() -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_COUNTER;
-    k
+fn get_caller() -> [u8; 20] {
+    let mut caller = [0u8; 20];
+    api::caller(&mut caller);
+    caller
 }
 
-fn key_form_cid(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_CID;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
+fn get_timestamp() -> U256 {
+    let mut ts = [0u8; 32];
+    api::now(&mut ts);
+    let mut arr = [0u8; 8];
+    arr.copy_from_slice(&ts[..8]);
+    u64::from_le_bytes(arr).into()
 }
 
-fn key_form_creator(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_FORM_CREATOR;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
-}
 
-fn key_response_counter(form_id: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_RESPONSE_COUNTER;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k
-}
+// ============ Contract functions ============
+
+/// Register a new form by storing its Bulletin CID
+fn register_form(form_cid: String) -> U256 {
+    if form_cid.is_empty() {
+        errors::revert("Invalid CID");
+    }
+
+    let form_id = FORM_COUNT.get_or_default() + 1;
+    let creator = get_caller();
+    FORM_COUNT.set(&form_id);
+    FORM_CIDS.set(&form_id, &form_cid);
+    FORM_CREATORS.set(&form_id, &creator);
 
-fn key_response_cid(form_id: u64, idx: u64) -> [u8; 32] {
-    let mut k = [0u8; 32];
-    k[0] = NS_RESPONSE_CID;
-    k[1..9].copy_from_slice(&form_id.to_le_bytes());
-    k[9..17].copy_from_slice(&idx.to_le_bytes());
-    k
+    FormRegistered { form_id, cid: form_cid, creator, timestamp: get_timestamp() }.emit();
+
+    form_id
 }
 
-// ============ Storage primitives (same pattern as test_minimal) ============
+/// Submit a response CID, returning its index in the form's response list
+fn submit_response(form_id: U256, response_cid: String) -> U256 {
+    if !form_exists(form_id) {
+        errors::revert("Form does not exist");
+    }
+    if response_cid.is_empty() {
+        errors::revert("Invalid CID");
+    }
+    if !is_eligible(form_id, get_caller()) {
+        errors::revert("Not eligible");
+    }
+
+    let idx = RESPONSE_CIDS.at(&form_id).push(&response_cid);
+    let timestamp = get_timestamp();
+    RESPONSE_RESPONDENTS.set(&(form_id, idx), &get_caller());
+    RESPONSE_TIMESTAMPS.set(&(form_id, idx), &timestamp);
+    let response_idx = U256::from(idx);
 
-fn get_u64(key: &[u8; 32]) -> u64 {
-    let mut buf = [0u8; 8];
-    api::get_storage(StorageFlags::empty(), key, &mut &mut buf[..]);
-    u64::from_le_bytes(buf)
+    ResponseSubmitted { form_id, response_idx, cid: response_cid, timestamp }.emit();
+
+    response_idx
 }
 
-fn set_u64(key: &[u8; 32], value: u64) {
-    api::set_storage(StorageFlags::empty(), key, &value.to_le_bytes());
+fn require_form_creator(form_id: U256) {
+    if !form_exists(form_id) {
+        errors::revert("Form does not exist");
+    }
+    if FORM_CREATORS.get(&form_id) != Some(get_caller()) {
+        errors::revert("Not form creator");
+    }
 }
 
-fn get_bytes(key: &[u8; 32]) -> Vec<u8> {
-    let mut buf = [0u8; 128];
-    match api::get_storage(StorageFlags::empty(), key, &mut &mut buf[..]) {
-        Ok(_) => {
-            // Find actual length (trim trailing zeros)
-            let len = buf.iter().rposition(|&b| b != 0).map_or(0, |p| p + 1);
-            buf[..len].to_vec()
+/// Restrict a form to allowlisted respondents and add `respondents`; an
+/// empty list leaves the form as it is
+fn add_to_allowlist(form_id: U256, respondents: Vec<[u8; 20]>) {
+    require_form_creator(form_id);
+    if !respondents.is_empty() {
+        ALLOWLIST_ENABLED.set(&form_id, &true);
+    }
+    for respondent in respondents {
+        if !ALLOWLIST.get_or_default(&(form_id, respondent)) {
+            ALLOWLIST.set(&(form_id, respondent), &true);
+            AllowlistUpdated { form_id, respondent, allowed: true }.emit();
         }
-        Err(_) => Vec::new(),
     }
 }
 
-fn set_bytes(key: &[u8; 32], data: &[u8]) {
-    api::set_storage(StorageFlags::empty(), key, data);
+/// Remove `respondents`; the form stays restricted even if none are left
+fn remove_from_allowlist(form_id: U256, respondents: Vec<[u8; 20]>) {
+    require_form_creator(form_id);
+    for respondent in respondents {
+        if ALLOWLIST.get_or_default(&(form_id, respondent)) {
+            ALLOWLIST.remove(&(form_id, respondent));
+            AllowlistUpdated { form_id, respondent, allowed: false }.emit();
+        }
+    }
 }
 
-fn get_caller() -> [u8; 20] {
-    let mut caller = [0u8; 20];
-    api::caller(&mut caller);
-    caller
+fn allowlist(form_id: U256, respondent: [u8; 20]) -> bool {
+    ALLOWLIST.get_or_default(&(form_id, respondent))
 }
 
-// ============ ABI parsing helpers ============
+fn allowlist_enabled(form_id: U256) -> bool {
+    ALLOWLIST_ENABLED.get_or_default(&form_id)
+}
 
-/// Read a uint64 from ABI-encoded data at the given 32-byte word offset.
-fn read_u64(data: &[u8], word: usize) -> u64 {
-    let off = word * 32;
-    if data.len() < off + 32 {
-        return 0;
-    }
-    // uint64 is right-aligned in 32 bytes — last 8 bytes
-    let mut arr = [0u8; 8];
-    arr.copy_from_slice(&data[off + 24..off + 32]);
-    u64::from_be_bytes(arr)
+/// Whether `respondent` may submit to the form
+fn is_eligible(form_id: U256, respondent: [u8; 20]) -> bool {
+    !allowlist_enabled(form_id) || allowlist(form_id, respondent)
 }
 
-/// Read ABI-encoded `bytes` parameter. Returns the raw byte content.
-/// ABI layout: word at `word_idx` contains offset → at that offset: length word → data.
-fn read_bytes(data: &[u8], word_idx: usize) -> Vec<u8> {
-    let ptr_off = word_idx * 32;
-    if data.len() < ptr_off + 32 {
-        return Vec::new();
-    }
-    // Read offset (big-endian uint256, but only last 4 bytes matter)
-    let mut off_arr = [0u8; 4];
-    off_arr.copy_from_slice(&data[ptr_off + 28..ptr_off + 32]);
-    let offset = u32::from_be_bytes(off_arr) as usize;
+fn form_cids(form_id: U256) -> String {
+    FORM_CIDS.get_or_default(&form_id)
+}
 
-    if data.len() < offset + 32 {
-        return Vec::new();
+fn response_cids(form_id: U256, idx: U256) -> String {
+    if idx.bits() > 64 {
+        errors::panic(errors::PANIC_ARRAY_OUT_OF_BOUNDS);
     }
-    // Read length
-    let mut len_arr = [0u8; 4];
-    len_arr.copy_from_slice(&data[offset + 28..offset + 32]);
-    let length = u32::from_be_bytes(len_arr) as usize;
-
-    let start = offset + 32;
-    let end = (start + length).min(data.len());
-    if start > data.len() {
-        return Vec::new();
+    match RESPONSE_CIDS.at(&form_id).get(idx.low_u64()) {
+        Some(cid) => cid,
+        None => errors::panic(errors::PANIC_ARRAY_OUT_OF_BOUNDS),
     }
-    data[start..end].to_vec()
 }
 
-/// Encode a uint256 (from u64) as 32 bytes big-endian.
-fn encode_u256(v: u64) -> [u8; 32] {
-    let mut out = [0u8; 32];
-    out[24..32].copy_from_slice(&v.to_be_bytes());
-    out
+fn form_creators(form_id: U256) -> [u8; 20] {
+    FORM_CREATORS.get_or_default(&form_id)
 }
 
-/// Encode `bytes` return value: offset (32) + length (32) + padded data.
-fn encode_bytes(data: &[u8]) -> Vec<u8> {
-    let padded_len = ((data.len() + 31) / 32) * 32;
-    let mut out = vec![0u8; 64 + padded_len];
-    // offset = 0x20
-    out[31] = 0x20;
-    // length
-    let len_bytes = (data.len() as u32).to_be_bytes();
-    out[60..64].copy_from_slice(&len_bytes);
-    // data
-    out[64..64 + data.len()].copy_from_slice(data);
-    out
+fn form_count() -> U256 {
+    FORM_COUNT.get_or_default()
 }
 
-// ============ Contract functions ============
+fn get_form_cid(form_id: U256) -> String {
+    form_cids(form_id)
+}
 
-fn register_form(cid: Vec<u8>) -> u64 {
-    if cid.is_empty() || cid.len() > 128 {
-        return 0;
+/// The form's response CIDs, as many from the first as fit `MAX_PAGE_BYTES`
+/// of return data; page through the rest with `getResponses`
+fn get_response_cids(form_id: U256) -> Vec<String> {
+    let responses = RESPONSE_CIDS.at(&form_id);
+    let mut cids = Vec::new();
+    let mut size = 64; // offset and length of the array
+    for idx in 0..responses.len() {
+        let cid = responses.get(idx).unwrap_or_default();
+        // Entry offset, cid length and padded cid
+        size += 2 * 32 + cid.len().div_ceil(32) * 32;
+        if size > MAX_PAGE_BYTES && !cids.is_empty() {
+            break;
+        }
+        cids.push(cid);
     }
-    let counter_key = key_form_counter();
-    let form_id = get_u64(&counter_key) + 1;
-    set_u64(&counter_key, form_id);
-    set_bytes(&key_form_cid(form_id), &cid);
-    set_bytes(&key_form_creator(form_id), &get_caller());
-    form_id
+    cids
 }
 
-fn submit_response(form_id: u64, cid: Vec<u8>) -> u64 {
-    if cid.is_empty() || cid.len() > 128 {
-        return u64::MAX;
-    }
-    // Check form exists
-    let form_cid = get_bytes(&key_form_cid(form_id));
-    if form_cid.is_empty() {
-        return u64::MAX;
+/// Up to `limit` responses from `offset` as `(idx, respondent, timestamp,
+/// cid)`. A page also ends once its return data would exceed
+/// `MAX_PAGE_BYTES`, so keep paging from `offset` plus the page length until
+/// `getResponseCount`.
+fn get_responses(form_id: U256, offset: U256, limit: U256) -> Vec<(U256, [u8; 20], U256, String)> {
+    let responses = RESPONSE_CIDS.at(&form_id);
+    let offset = clamp_u64(offset);
+    let end = offset.saturating_add(clamp_u64(limit)).min(responses.len());
+    let mut page = Vec::new();
+    let mut size = 64; // offset and length of the array
+    for idx in offset..end {
+        let cid = responses.get(idx).unwrap_or_default();
+        // Entry offset, tuple head, cid length and padded cid
+        size += 6 * 32 + cid.len().div_ceil(32) * 32;
+        // Always return at least one response so paging makes progress
+        if size > MAX_PAGE_BYTES && !page.is_empty() {
+            break;
+        }
+        let respondent = RESPONSE_RESPONDENTS.get_or_default(&(form_id, idx));
+        let timestamp = RESPONSE_TIMESTAMPS.get_or_default(&(form_id, idx));
+        page.push((idx.into(), respondent, timestamp, cid));
     }
-    let counter_key = key_response_counter(form_id);
-    let idx = get_u64(&counter_key);
-    set_u64(&counter_key, idx + 1);
-    set_bytes(&key_response_cid(form_id, idx), &cid);
-    idx
+    page
 }
 
-fn get_form_cid(form_id: u64) -> Vec<u8> {
-    get_bytes(&key_form_cid(form_id))
+fn clamp_u64(value: U256) -> u64 {
+    if value.bits() > 64 { u64::MAX } else { value.low_u64() }
 }
 
-fn get_response_cid(form_id: u64, idx: u64) -> Vec<u8> {
-    get_bytes(&key_response_cid(form_id, idx))
+fn get_response_count(form_id: U256) -> U256 {
+    RESPONSE_CIDS.at(&form_id).len().into()
 }
 
-fn form_count() -> u64 {
-    get_u64(&key_form_counter())
+fn get_form_creator(form_id: U256) -> [u8; 20] {
+    form_creators(form_id)
 }
 
-fn response_count(form_id: u64) -> u64 {
-    get_u64(&key_response_counter(form_id))
+fn form_exists(form_id: U256) -> bool {
+    FORM_CIDS.contains(&form_id)
 }
 
 // ============ Dispatcher ============
 
-#[polkavm_export]
+// Same selectors as FormsV2.sol, including its public state variable getters
+dispatch! {
+    fn route;
+
+    #[selector("registerForm(string)")]
+    fn register_form(form_cid: String) -> U256;
+
+    #[selector("submitResponse(uint256,string)")]
+    fn submit_response(form_id: U256, response_cid: String) -> U256;
+
+    #[selector("formCids(uint256)")]
+    fn form_cids(form_id: U256) -> String;
+
+    #[selector("responseCids(uint256,uint256)")]
+    fn response_cids(form_id: U256, idx: U256) -> String;
+
+    #[selector("formCreators(uint256)")]
+    fn form_creators(form_id: U256) -> [u8; 20];
+
+    #[selector("formCount()")]
+    fn form_count() -> U256;
+
+    #[selector("allowlist(uint256,address)")]
+    fn allowlist(form_id: U256, respondent: [u8; 20]) -> bool;
+
+    #[selector("allowlistEnabled(uint256)")]
+    fn allowlist_enabled(form_id: U256) -> bool;
+
+    #[selector("addToAllowlist(uint256,address[])")]
+    fn add_to_allowlist(form_id: U256, respondents: Vec<[u8; 20]>);
+
+    #[selector("removeFromAllowlist(uint256,address[])")]
+    fn remove_from_allowlist(form_id: U256, respondents: Vec<[u8; 20]>);
+
+    #[selector("isEligible(uint256,address)")]
+    fn is_eligible(form_id: U256, respondent: [u8; 20]) -> bool;
+
+    #[selector("getFormCid(uint256)")]
+    fn get_form_cid(form_id: U256) -> String;
+
+    #[selector("getResponseCids(uint256)")]
+    fn get_response_cids(form_id: U256) -> Vec<String>;
+
+    #[selector("getResponses(uint256,uint256,uint256)")]
+    fn get_responses(form_id: U256, offset: U256, limit: U256) -> Vec<(U256, [u8; 20], U256, String)>;
+
+    #[selector("getResponseCount(uint256)")]
+    fn get_response_count(form_id: U256) -> U256;
+
+    #[selector("getFormCreator(uint256)")]
+    fn get_form_creator(form_id: U256) -> [u8; 20];
+
+    #[selector("formExists(uint256)")]
+    fn form_exists(form_id: U256) -> bool;
+}
+
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn call() {
+    dispatch();
+}
+
+/// Selector dispatch behind `call()`, callable from host-side tests.
+///
+/// Like a Solidity contract without a fallback, short calldata, unknown
+/// selectors and malformed arguments revert with empty data.
+fn dispatch() {
     let length = api::call_data_size() as usize;
     if length < 4 {
-        api::return_value(ReturnFlags::REVERT, b"Input too short");
-        return;
+        errors::revert_with(&[]);
     }
 
-    let mut selector = [0u8; 4];
-    api::call_data_copy(&mut selector, 0);
-
-    // Read remaining calldata on heap (NOT stack — critical for PolkaVM)
-    let data_len = length.saturating_sub(4).min(1024);
-    let data = if data_len > 0 {
-        let mut buf = vec![0u8; data_len];
-        api::call_data_copy(&mut buf, 4);
-        buf
-    } else {
-        Vec::new()
-    };
-
-    match selector {
-        SEL_REGISTER_FORM => {
-            let cid = read_bytes(&data, 0);
-            let form_id = register_form(cid);
-            api::return_value(ReturnFlags::empty(), &encode_u256(form_id));
-        }
-        SEL_SUBMIT_RESPONSE => {
-            let form_id = read_u64(&data, 0);
-            let cid = read_bytes(&data, 1);
-            let idx = submit_response(form_id, cid);
-            api::return_value(ReturnFlags::empty(), &encode_u256(idx));
-        }
-        SEL_GET_FORM_CID => {
-            let form_id = read_u64(&data, 0);
-            let cid = get_form_cid(form_id);
-            api::return_value(ReturnFlags::empty(), &encode_bytes(&cid));
-        }
-        SEL_GET_RESPONSE_CID => {
-            let form_id = read_u64(&data, 0);
-            let idx = read_u64(&data, 1);
-            let cid = get_response_cid(form_id, idx);
-            api::return_value(ReturnFlags::empty(), &encode_bytes(&cid));
-        }
-        SEL_FORM_COUNT => {
-            let count = form_count();
-            api::return_value(ReturnFlags::empty(), &encode_u256(count));
-        }
-        SEL_RESPONSE_COUNT => {
-            let form_id = read_u64(&data, 0);
-            let count = response_count(form_id);
-            api::return_value(ReturnFlags::empty(), &encode_u256(count));
-        }
-        _ => {
-            api::return_value(ReturnFlags::REVERT, b"Unknown selector");
-        }
+    // Read call data on the heap, not the stack; oversized call data is bad
+    // call data, not something to truncate
+    if length > MAX_CALL_DATA_LEN {
+        errors::revert_with(&[]);
+    }
+    let mut input = vec![0u8; length];
+    api::call_data_copy(&mut input, 0);
+
+    let selector: [u8; 4] = [input[0], input[1], input[2], input[3]];
+
+    match route(selector, &input[4..]) {
+        Ok(output) => api::return_value(ReturnFlags::empty(), &output),
+        Err(DispatchError::UnknownSelector | DispatchError::Decode) => errors::revert_with(&[]),
     }
 }
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn deploy() {}
+
+#[cfg(feature = "mock")]
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethabi::{encode, Token};
+    use intran3t_polkavm_contracts::abi::selector;
+    use intran3t_polkavm_contracts::mock::{self, address, call, query, string, uint};
+
+    const CREATOR: [u8; 20] = [0x11; 20];
+    const ALICE: [u8; 20] = [0x22; 20];
+
+    fn setup() {
+        mock::reset();
+        mock::deploy(dispatch, HEAP_SIZE);
+        mock::set_caller(CREATOR);
+        mock::set_now(1_000);
+    }
+
+    fn register(cid: &str) -> U256 {
+        query("registerForm(string)", &[string(cid)])
+    }
+
+    fn submit(form_id: u64, cid: &str) -> mock::Outcome {
+        call("submitResponse(uint256,string)", &[uint(form_id), string(cid)])
+    }
+
+    #[test]
+    fn register_and_respond() {
+        setup();
+        assert_eq!(register("bafy-form"), 1.into());
+        assert_eq!(query::<U256>("formCount()", &[]), 1.into());
+        assert_eq!(query::<String>("getFormCid(uint256)", &[uint(1)]), "bafy-form");
+        assert_eq!(query::<String>("formCids(uint256)", &[uint(1)]), "bafy-form");
+        assert_eq!(query::<[u8; 20]>("getFormCreator(uint256)", &[uint(1)]), CREATOR);
+        assert_eq!(query::<[u8; 20]>("formCreators(uint256)", &[uint(1)]), CREATOR);
+        assert!(query::<bool>("formExists(uint256)", &[uint(1)]));
+        assert!(!query::<bool>("formExists(uint256)", &[uint(2)]));
+
+        mock::set_caller(ALICE);
+        assert_eq!(submit(1, "r0").data, encode(&[uint(0)]));
+        assert_eq!(submit(1, "r1").data, encode(&[uint(1)]));
+        assert_eq!(query::<U256>("getResponseCount(uint256)", &[uint(1)]), 2.into());
+        assert_eq!(query::<Vec<String>>("getResponseCids(uint256)", &[uint(1)]), ["r0", "r1"]);
+        assert_eq!(query::<String>("responseCids(uint256,uint256)", &[uint(1), uint(1)]), "r1");
+
+        let events = mock::take_events();
+        let topics: Vec<_> = events.iter().map(|event| event.topics[0]).collect();
+        assert_eq!(topics, [FormRegistered::TOPIC, ResponseSubmitted::TOPIC, ResponseSubmitted::TOPIC]);
+        // FormRegistered(uint256 indexed formId, string cid, address indexed creator, uint256 timestamp)
+        assert_eq!(events[0].topics[2][12..], CREATOR);
+    }
+
+    #[test]
+    fn reverts_match_solidity() {
+        setup();
+        assert_eq!(call("registerForm(string)", &[string("")]).data, errors::error_string("Invalid CID"));
+        assert_eq!(submit(1, "r0").data, errors::error_string("Form does not exist"));
+        register("bafy-form");
+        assert_eq!(submit(1, "").data, errors::error_string("Invalid CID"));
+
+        let mut panic_data = errors::PANIC_SELECTOR.to_vec();
+        panic_data.extend(encode(&[uint(errors::PANIC_ARRAY_OUT_OF_BOUNDS as u64)]));
+        let out = call("responseCids(uint256,uint256)", &[uint(1), uint(0)]);
+        assert_eq!(out, mock::Outcome { reverted: true, data: panic_data });
+        assert_eq!(call("noSuchFunction()", &[]), mock::Outcome { reverted: true, data: Vec::new() });
+    }
+
+    #[test]
+    fn empty_allowlists_leave_the_form_open() {
+        setup();
+        register("bafy-form");
+        let add = |respondents: Vec<Token>| call("addToAllowlist(uint256,address[])", &[uint(1), Token::Array(respondents)]);
+        assert!(!add(Vec::new()).reverted);
+        assert!(!query::<bool>("allowlistEnabled(uint256)", &[uint(1)]));
+        assert!(query::<bool>("isEligible(uint256,address)", &[uint(1), address(ALICE)]));
+
+        assert!(!add(vec![address(CREATOR)]).reverted);
+        assert!(query::<bool>("allowlistEnabled(uint256)", &[uint(1)]));
+        assert!(!query::<bool>("isEligible(uint256,address)", &[uint(1), address(ALICE)]));
+    }
+
+    #[test]
+    fn call_data_is_read_whole_up_to_the_bound() {
+        setup();
+        register("bafy-form");
+        // The largest allowlist batch that fits the bound
+        let count = (MAX_CALL_DATA_LEN - 4 - 3 * 32) / 32;
+        let respondents: Vec<Token> = (0..count).map(|i| address([i as u8 + 1; 20])).collect();
+        let args = [uint(1), Token::Array(respondents)];
+        assert!(encode(&args).len() + 4 <= MAX_CALL_DATA_LEN);
+        assert!(!call("addToAllowlist(uint256,address[])", &args).reverted);
+        assert!(query::<bool>("isEligible(uint256,address)", &[uint(1), address([count as u8; 20])]));
+
+        let mut input = selector("formCount()").to_vec();
+        input.resize(MAX_CALL_DATA_LEN + 1, 0);
+        assert_eq!(mock::execute(dispatch, &input), mock::Outcome { reverted: true, data: Vec::new() });
+    }
+
+    #[test]
+    fn response_pages_stop_at_the_byte_budget() {
+        setup();
+        register("bafy-form");
+        // The longest CID that fits the call data bound, then short ones
+        let long = "c".repeat(MAX_CALL_DATA_LEN - 4 - 4 * 32);
+        for cid in [long, "c".repeat(100)] {
+            for _ in 0..30 {
+                assert!(!submit(1, &cid).reverted);
+            }
+        }
+        type Page = Vec<(U256, [u8; 20], U256, String)>;
+        let page = |offset: u64| query::<Page>("getResponses(uint256,uint256,uint256)", &[uint(1), uint(offset), uint(60)]);
+
+        // Two long entries don't fit one page, so each starts a page of its own;
+        // after the last one, short entries fill the rest of the budget
+        assert_eq!(page(0).len(), 1);
+        assert_eq!(page(29).len(), 13);
+        // Each short entry takes 6 * 32 + 128 bytes
+        let full = page(30);
+        mock::assert_full_page(full.len(), 6 * 32 + 128);
+        assert_eq!(full[0], (30.into(), CREATOR, 1_000.into(), "c".repeat(100)));
+    }
+
+    #[test]
+    fn response_cids_stop_at_the_byte_budget() {
+        setup();
+        register("bafy-form");
+        for idx in 0..60 {
+            assert!(!submit(1, &format!("{idx:0>100}")).reverted);
+        }
+        // Each entry takes 2 * 32 + 128 bytes
+        let cids = query::<Vec<String>>("getResponseCids(uint256)", &[uint(1)]);
+        mock::assert_full_page(cids.len(), 2 * 32 + 128);
+        assert_eq!(cids[41], format!("{:0>100}", 41));
+    }
+}
@@ -0,0 +1,13 @@
+//! Host function backend used by the contracts.
+//!
+//! [`Api`] is `uapi::HostFnImpl` when building for PolkaVM and
+//! [`crate::mock::MockApi`] when the `mock` feature is enabled, so the same
+//! contract code runs on chain and under `cargo test` on the host.
+
+pub use uapi::{CallFlags, ReturnErrorCode, ReturnFlags, StorageFlags};
+
+#[cfg(not(feature = "mock"))]
+pub use uapi::{HostFn, HostFnImpl as Api};
+
+#[cfg(feature = "mock")]
+pub use crate::mock::MockApi as Api;
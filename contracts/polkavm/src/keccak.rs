@@ -0,0 +1,158 @@
+//! `const fn` Keccak-256, so selectors and event topics can be derived from
+//! their Solidity signatures at compile time, plus [`hash`] for runtime
+//! hashing through the host.
+//!
+//! This is the original Keccak padding used by Ethereum (`0x01`), not the
+//! NIST SHA3-256 padding (`0x06`).
+
+#[cfg(not(feature = "mock"))]
+use crate::host::HostFn;
+use crate::host::Api;
+
+const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+const ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+const fn keccak_f(mut state: [u64; 25]) -> [u64; 25] {
+    let mut round = 0;
+    while round < 24 {
+        // Theta
+        let mut columns = [0u64; 5];
+        let mut x = 0;
+        while x < 5 {
+            columns[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+            x += 1;
+        }
+        x = 0;
+        while x < 5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            let mut y = 0;
+            while y < 25 {
+                state[y + x] ^= d;
+                y += 5;
+            }
+            x += 1;
+        }
+
+        // Rho and Pi
+        let mut carry = state[1];
+        let mut i = 0;
+        while i < 24 {
+            let lane = PI_LANES[i];
+            let next = state[lane];
+            state[lane] = carry.rotate_left(ROTATIONS[i]);
+            carry = next;
+            i += 1;
+        }
+
+        // Chi
+        let mut y = 0;
+        while y < 25 {
+            let row = [state[y], state[y + 1], state[y + 2], state[y + 3], state[y + 4]];
+            x = 0;
+            while x < 5 {
+                state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+                x += 1;
+            }
+            y += 5;
+        }
+
+        // Iota
+        state[0] ^= ROUND_CONSTANTS[round];
+        round += 1;
+    }
+    state
+}
+
+const fn absorb(mut state: [u64; 25], block: &[u8], offset: usize) -> [u64; 25] {
+    let mut i = 0;
+    while i < RATE {
+        state[i / 8] ^= (block[offset + i] as u64) << (8 * (i % 8));
+        i += 1;
+    }
+    keccak_f(state)
+}
+
+/// Keccak-256 hash of `input`.
+pub const fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut state = [0u64; 25];
+
+    let mut offset = 0;
+    while input.len() - offset >= RATE {
+        state = absorb(state, input, offset);
+        offset += RATE;
+    }
+
+    // Final block: remaining input plus multi-rate padding
+    let mut last = [0u8; RATE];
+    let mut i = 0;
+    while offset + i < input.len() {
+        last[i] = input[offset + i];
+        i += 1;
+    }
+    last[i] ^= 0x01;
+    last[RATE - 1] ^= 0x80;
+    state = absorb(state, &last, 0);
+
+    let mut out = [0u8; 32];
+    i = 0;
+    while i < 32 {
+        out[i] = (state[i / 8] >> (8 * (i % 8))) as u8;
+        i += 1;
+    }
+    out
+}
+
+/// Keccak-256 via the `hash_keccak_256` host function; cheaper than
+/// [`keccak256`] for values only known at runtime.
+pub fn hash(input: &[u8]) -> [u8; 32] {
+    let mut output = [0u8; 32];
+    Api::hash_keccak_256(input, &mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::selector;
+
+    fn hex(hash: [u8; 32]) -> alloc::string::String {
+        hash.iter().map(|byte| alloc::format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    fn known_answers() {
+        assert_eq!(hex(keccak256(b"")), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(hex(keccak256(b"abc")), "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45");
+    }
+
+    #[test]
+    fn inputs_around_the_rate() {
+        // One byte short of a block, exactly one block (padding fills a second
+        // block) and one byte over
+        assert_eq!(hex(keccak256(&[b'a'; RATE - 1])), "34367dc248bbd832f4e3e69dfaac2f92638bd0bbd18f2912ba4ef454919cf446");
+        assert_eq!(hex(keccak256(&[b'a'; RATE])), "a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e");
+        assert_eq!(hex(keccak256(&[b'a'; RATE + 1])), "d869f639c7046b4929fc92a4d988a8b22c55fbadb802c0c66ebcd484f1915f39");
+    }
+
+    #[test]
+    fn selectors_are_computed_at_compile_time() {
+        const TRANSFER: [u8; 4] = selector("transfer(address,uint256)");
+        assert_eq!(TRANSFER, [0xa9, 0x05, 0x9c, 0xbb]);
+    }
+}
@@ -1,10 +1,20 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
 
 pub mod storage;
+pub mod codec;
+pub mod collections;
 pub mod abi;
+pub mod keccak;
+pub mod merkle;
+pub mod events;
+pub mod errors;
+pub mod calls;
+pub mod personhood;
+pub mod host;
+#[cfg(feature = "mock")]
+pub mod mock;
 
 // Re-export common types for contract modules
 pub use uapi as api;
@@ -0,0 +1,124 @@
+//! Merkle membership proofs, compatible with OpenZeppelin's `MerkleProof`
+//! and the trees built by `@openzeppelin/merkle-tree`.
+//!
+//! Pairs are hashed in sorted order, so a proof is just the list of sibling
+//! hashes from the leaf up to the root, without left/right flags.
+
+use crate::keccak;
+
+/// Root implied by `leaf` and `proof`.
+pub fn process_proof(proof: &[[u8; 32]], leaf: [u8; 32]) -> [u8; 32] {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling))
+}
+
+/// Whether `proof` shows that `leaf` is part of the tree with `root`.
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    process_proof(proof, leaf) == *root
+}
+
+/// Leaf of `address` in a `StandardMerkleTree.of(values, ["address"])`:
+/// `keccak256(keccak256(abi.encode(address)))`.
+pub fn address_leaf(address: &[u8; 20]) -> [u8; 32] {
+    let mut encoded = [0u8; 32];
+    encoded[12..].copy_from_slice(address);
+    keccak::hash(&keccak::hash(&encoded))
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(first);
+    preimage[32..].copy_from_slice(second);
+    keccak::hash(&preimage)
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn hex32(hex: &str) -> [u8; 32] {
+        let hex = hex.trim_start_matches("0x");
+        core::array::from_fn(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+    }
+
+    fn address(byte: u8) -> [u8; 20] {
+        [byte; 20]
+    }
+
+    /// `@openzeppelin/merkle-tree`'s `makeMerkleTree` over sorted leaves:
+    /// leaves fill the end of the array in reverse, each node hashes its two
+    /// children
+    fn standard_tree(mut leaves: Vec<[u8; 32]>) -> Vec<[u8; 32]> {
+        leaves.sort();
+        let mut tree = alloc::vec![[0u8; 32]; 2 * leaves.len() - 1];
+        let len = tree.len();
+        for (i, leaf) in leaves.into_iter().enumerate() {
+            tree[len - 1 - i] = leaf;
+        }
+        for i in (0..len / 2).rev() {
+            tree[i] = hash_pair(&tree[2 * i + 1], &tree[2 * i + 2]);
+        }
+        tree
+    }
+
+    /// `getProof` for the node at `index` of a tree built by `standard_tree`
+    fn standard_proof(tree: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        while index > 0 {
+            let sibling = if index % 2 == 1 { index + 1 } else { index - 1 };
+            proof.push(tree[sibling]);
+            index = (index - 1) / 2;
+        }
+        proof
+    }
+
+    #[test]
+    fn matches_the_openzeppelin_readme_tree() {
+        // StandardMerkleTree.of([[0x1111…, "5000000000000000000"],
+        // [0x2222…, "2500000000000000000"]], ["address", "uint256"])
+        let leaf = |address: [u8; 20], amount: u64| {
+            let mut encoded = [0u8; 64];
+            encoded[12..32].copy_from_slice(&address);
+            encoded[56..].copy_from_slice(&amount.to_be_bytes());
+            keccak::hash(&keccak::hash(&encoded))
+        };
+        let first = leaf(address(0x11), 5_000_000_000_000_000_000);
+        let second = leaf(address(0x22), 2_500_000_000_000_000_000);
+        let root = hex32("0xd4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77");
+
+        assert_eq!(second, hex32("0xb92c48e9d7abe27fd8dfd6b5dfdbfb1c9a463f80c712b66f3a5180a090cccafc"));
+        assert!(verify(&[second], &root, first));
+        assert!(verify(&[first], &root, second));
+        assert_eq!(standard_tree(alloc::vec![first, second])[0], root);
+    }
+
+    #[test]
+    fn verifies_every_leaf_of_standard_trees() {
+        for count in 1..=9u8 {
+            let leaves: Vec<_> = (1..=count).map(|byte| address_leaf(&address(byte))).collect();
+            let tree = standard_tree(leaves.clone());
+            for leaf in &leaves {
+                let index = tree.iter().rposition(|node| node == leaf).unwrap();
+                let proof = standard_proof(&tree, index);
+                assert!(verify(&proof, &tree[0], *leaf), "leaf {index} of {count}");
+                // The proof is tied to its leaf
+                assert!(!verify(&proof, &tree[0], address_leaf(&address(0xee))));
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_proofs_fail() {
+        let leaves: Vec<_> = (1..=4u8).map(|byte| address_leaf(&address(byte))).collect();
+        let tree = standard_tree(leaves.clone());
+        let index = tree.iter().rposition(|node| *node == leaves[0]).unwrap();
+        let proof = standard_proof(&tree, index);
+
+        let mut flipped = proof.clone();
+        flipped[1][0] ^= 1;
+        assert!(!verify(&flipped, &tree[0], leaves[0]));
+        assert!(!verify(&proof[..1], &tree[0], leaves[0]));
+        assert!(!verify(&proof, &tree[0], leaves[1]));
+    }
+}
@@ -0,0 +1,545 @@
+//! Host-side mock of the pallet-revive host functions.
+//!
+//! Enabled with the `mock` feature. [`MockApi`] mirrors the subset of
+//! `uapi::HostFn` used by the contracts, backed by thread-local in-memory
+//! state, so a contract dispatcher can be driven from `cargo test` on the
+//! host without deploying to a chain:
+//!
+//! ```ignore
+//! mock::reset();
+//! mock::deploy(dispatch, HEAP_SIZE);
+//! mock::set_caller([0x11; 20]);
+//! let out = mock::call("registerForm(string)", &[mock::string("bafy")]);
+//! assert!(!out.reverted);
+//! assert_eq!(mock::events().len(), 1);
+//! ```
+//!
+//! `return_value` never returns on chain; here it unwinds back to
+//! [`execute`], which reports the return data and revert flag. Storage and
+//! events written by a reverted call are rolled back, like on chain.
+//!
+//! The mock also installs a counting global allocator, so tests can check
+//! with [`heap_used`] that a call fits the contract's on-chain heap.
+
+extern crate std;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::{Cell, RefCell};
+use std::alloc::System;
+use std::panic::{self, AssertUnwindSafe};
+use ethabi::{decode, Token};
+use uapi::{CallFlags, ReturnErrorCode, ReturnFlags, StorageFlags};
+
+use crate::abi::{encode, selector, SolType, MAX_PAGE_BYTES};
+
+// ============ State ============
+
+/// An event deposited through `deposit_event`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+/// Result of running an entry point under [`execute`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub reverted: bool,
+    pub data: Vec<u8>,
+}
+
+/// Stand-in for another contract: maps call data to its outcome.
+type ContractHandler = Rc<dyn Fn(&[u8]) -> Outcome>;
+
+#[derive(Default)]
+struct State {
+    storage: BTreeMap<Vec<u8>, Vec<u8>>,
+    transient: BTreeMap<Vec<u8>, Vec<u8>>,
+    caller: [u8; 20],
+    now: u64,
+    call_data: Vec<u8>,
+    events: Vec<Event>,
+    contracts: BTreeMap<[u8; 20], ContractHandler>,
+    /// Entry point and heap size set by `deploy`
+    deployed: Option<(fn(), usize)>,
+    return_data: Vec<u8>,
+    heap_used: usize,
+}
+
+std::thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Panic payload used to unwind out of `return_value`.
+struct ReturnSignal {
+    reverted: bool,
+    data: Vec<u8>,
+}
+
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> R {
+    // The mock's own bookkeeping is not part of the contract's heap
+    let counting = COUNTING.replace(false);
+    let result = STATE.with(|s| f(&mut s.borrow_mut()));
+    COUNTING.set(counting);
+    result
+}
+
+// ============ Heap Accounting ============
+
+/// Global allocator that counts the bytes allocated by a contract under
+/// [`execute`]. On chain, contracts use `simplealloc`, a bump allocator that
+/// never frees, so every allocation of a call adds to its peak heap use.
+struct CountingAlloc;
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+std::thread_local! {
+    /// Whether allocations are currently made by the contract
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static HEAP_USED: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count(layout: Layout) {
+    // Thread-locals are gone while a thread shuts down
+    let _ = COUNTING.try_with(|counting| {
+        if counting.get() {
+            HEAP_USED.with(|used| used.set(used.get() + layout.pad_to_align().size()));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // A bump allocator grows by allocating anew and copying
+        count(Layout::from_size_align_unchecked(new_size, layout.align()));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+// ============ Test Controls ============
+
+/// Clear all storage, events and environment values.
+pub fn reset() {
+    with_state(|s| *s = State::default());
+}
+
+/// Set the address returned by `caller`.
+pub fn set_caller(caller: [u8; 20]) {
+    with_state(|s| s.caller = caller);
+}
+
+/// Set the block timestamp returned by `now`.
+pub fn set_now(now: u64) {
+    with_state(|s| s.now = now);
+}
+
+/// Read a raw storage value.
+pub fn storage(key: &[u8]) -> Option<Vec<u8>> {
+    with_state(|s| s.storage.get(key).cloned())
+}
+
+/// Write a raw storage value, bypassing the contract.
+pub fn set_storage(key: &[u8], value: &[u8]) {
+    with_state(|s| {
+        s.storage.insert(key.to_vec(), value.to_vec());
+    });
+}
+
+/// Answer calls to `address` with `handler`, e.g. to stand in for another
+/// contract or a precompile. Calls to other addresses succeed with empty
+/// output, like calls to an account without code.
+pub fn register_contract(address: [u8; 20], handler: impl Fn(&[u8]) -> Outcome + 'static) {
+    with_state(|s| {
+        s.contracts.insert(address, Rc::new(handler));
+    });
+}
+
+/// All events deposited since the last [`reset`] or [`take_events`].
+pub fn events() -> Vec<Event> {
+    with_state(|s| s.events.clone())
+}
+
+/// Drain the deposited events.
+pub fn take_events() -> Vec<Event> {
+    with_state(|s| core::mem::take(&mut s.events))
+}
+
+/// Heap bytes allocated by the contract during the last [`execute`], i.e.
+/// its peak heap use under a bump allocator. Allocations made by the mock
+/// and by registered contract handlers are not counted.
+pub fn heap_used() -> usize {
+    with_state(|s| s.heap_used)
+}
+
+/// Run a contract entry point with `input` as call data.
+///
+/// Returns the data passed to `return_value` (empty if the entry point
+/// returned normally). Any other panic is treated as a trap: state is rolled
+/// back and the panic is propagated to the test.
+pub fn execute(entry: fn(), input: &[u8]) -> Outcome {
+    let (storage, event_count) = with_state(|s| {
+        s.call_data = input.to_vec();
+        s.transient.clear();
+        (s.storage.clone(), s.events.len())
+    });
+    let rollback = move || {
+        with_state(|s| {
+            s.storage = storage;
+            s.events.truncate(event_count);
+        })
+    };
+
+    HEAP_USED.set(0);
+    COUNTING.set(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(entry));
+    COUNTING.set(false);
+    let heap_used = HEAP_USED.get();
+    with_state(|s| s.heap_used = heap_used);
+
+    match result {
+        Ok(()) => Outcome { reverted: false, data: Vec::new() },
+        Err(payload) => match payload.downcast::<ReturnSignal>() {
+            Ok(signal) => {
+                if signal.reverted {
+                    rollback();
+                }
+                Outcome { reverted: signal.reverted, data: signal.data }
+            }
+            Err(payload) => {
+                rollback();
+                panic::resume_unwind(payload)
+            }
+        },
+    }
+}
+
+// ============ Contract Calls ============
+
+/// Make `entry` the contract that [`call`], [`call_raw`] and [`query`] run.
+///
+/// On chain, contracts use `simplealloc`, which never frees, so a call fails
+/// once it has allocated `heap_size` bytes in total. Calls through these
+/// helpers panic if the call allocated more, so every test call checks the
+/// contract's heap size.
+pub fn deploy(entry: fn(), heap_size: usize) {
+    with_state(|s| s.deployed = Some((entry, heap_size)));
+}
+
+/// Run the deployed contract with raw call data and check its heap use.
+pub fn call_raw(input: &[u8]) -> Outcome {
+    let (entry, heap_size) = with_state(|s| s.deployed).expect("no contract deployed, see `mock::deploy`");
+    let out = execute(entry, input);
+    assert!(heap_used() <= heap_size, "call used {} bytes of the {heap_size} byte heap", heap_used());
+    out
+}
+
+/// Call the deployed contract's function `signature` with ABI-encoded `args`.
+pub fn call(signature: &str, args: &[Token]) -> Outcome {
+    let mut input = selector(signature).to_vec();
+    input.extend(encode(args));
+    call_raw(&input)
+}
+
+/// Call a function that must succeed and decode its return value.
+pub fn query<R: SolType>(signature: &str, args: &[Token]) -> R {
+    let out = call(signature, args);
+    assert!(!out.reverted, "{signature} reverted with {:?}", out.data);
+    let token = decode(&[R::param_type()], &out.data).unwrap().remove(0);
+    R::from_token(token).unwrap()
+}
+
+/// Check that the last call returned a page of `len` entries, each taking
+/// `entry_len` bytes of return data, as full as [`MAX_PAGE_BYTES`] allows,
+/// and that it fit in 32 KiB of heap.
+pub fn assert_full_page(len: usize, entry_len: usize) {
+    assert!(heap_used() <= 32 * 1024, "a full page used {} bytes of heap", heap_used());
+    // The array's offset and length words come first
+    assert_eq!(len, (MAX_PAGE_BYTES - 64) / entry_len);
+}
+
+pub fn uint(value: u64) -> Token {
+    Token::Uint(value.into())
+}
+
+pub fn address(value: [u8; 20]) -> Token {
+    Token::Address(value.into())
+}
+
+pub fn bytes(value: &[u8]) -> Token {
+    Token::Bytes(value.to_vec())
+}
+
+pub fn bytes32(value: [u8; 32]) -> Token {
+    Token::FixedBytes(value.to_vec())
+}
+
+pub fn string(value: &str) -> Token {
+    Token::String(value.into())
+}
+
+// ============ Host Functions ============
+
+/// In-memory stand-in for `uapi::HostFnImpl`.
+///
+/// Signatures match the `HostFn` trait so contracts can switch backends
+/// through [`crate::host::Api`] without code changes.
+pub enum MockApi {}
+
+impl MockApi {
+    pub fn caller(output: &mut [u8; 20]) {
+        *output = with_state(|s| s.caller);
+    }
+
+    pub fn now(output: &mut [u8; 32]) {
+        let now = with_state(|s| s.now);
+        *output = [0u8; 32];
+        output[..8].copy_from_slice(&now.to_le_bytes());
+    }
+
+    pub fn call_data_size() -> u64 {
+        with_state(|s| s.call_data.len() as u64)
+    }
+
+    pub fn call_data_copy(output: &mut [u8], offset: u32) {
+        with_state(|s| {
+            // Bytes past the end of the call data read as zero
+            output.fill(0);
+            let start = (offset as usize).min(s.call_data.len());
+            let available = &s.call_data[start..];
+            let len = available.len().min(output.len());
+            output[..len].copy_from_slice(&available[..len]);
+        })
+    }
+
+    pub fn get_storage(
+        flags: StorageFlags,
+        key: &[u8],
+        output: &mut &mut [u8],
+    ) -> Result<(), ReturnErrorCode> {
+        let value = with_state(|s| {
+            let map = if flags.contains(StorageFlags::TRANSIENT) { &s.transient } else { &s.storage };
+            map.get(key).cloned()
+        });
+        let value = value.ok_or(ReturnErrorCode::KeyNotFound)?;
+        // The runtime traps when the value does not fit the output buffer
+        assert!(value.len() <= output.len(), "get_storage: output buffer too small");
+        let out = core::mem::take(output);
+        out[..value.len()].copy_from_slice(&value);
+        *output = &mut out[..value.len()];
+        Ok(())
+    }
+
+    pub fn set_storage(flags: StorageFlags, key: &[u8], value: &[u8]) -> Option<u32> {
+        with_state(|s| {
+            let map = if flags.contains(StorageFlags::TRANSIENT) { &mut s.transient } else { &mut s.storage };
+            // Writing an empty value deletes the entry
+            let previous = if value.is_empty() {
+                map.remove(key)
+            } else {
+                map.insert(key.to_vec(), value.to_vec())
+            };
+            previous.map(|v| v.len() as u32)
+        })
+    }
+
+    pub fn deposit_event(topics: &[[u8; 32]], data: &[u8]) {
+        with_state(|s| s.events.push(Event { topics: topics.to_vec(), data: data.to_vec() }));
+    }
+
+    pub fn hash_keccak_256(input: &[u8], output: &mut [u8; 32]) {
+        *output = crate::keccak::keccak256(input);
+    }
+
+    /// Runs the handler registered for `callee`; flags, limits and value are
+    /// ignored.
+    #[allow(clippy::too_many_arguments)]
+    pub fn call(
+        _flags: CallFlags,
+        callee: &[u8; 20],
+        _ref_time_limit: u64,
+        _proof_size_limit: u64,
+        _deposit: &[u8; 32],
+        _value: &[u8; 32],
+        input_data: &[u8],
+        output: Option<&mut &mut [u8]>,
+    ) -> Result<(), ReturnErrorCode> {
+        let handler = with_state(|s| s.contracts.get(callee).cloned());
+        // The callee runs with its own heap
+        let counting = COUNTING.replace(false);
+        let outcome = match handler {
+            Some(handler) => handler(input_data),
+            None => Outcome { reverted: false, data: Vec::new() },
+        };
+        COUNTING.set(counting);
+        if let Some(output) = output {
+            // The runtime traps when the output does not fit the buffer
+            assert!(outcome.data.len() <= output.len(), "call: output buffer too small");
+            let out = core::mem::take(output);
+            out[..outcome.data.len()].copy_from_slice(&outcome.data);
+            *output = &mut out[..outcome.data.len()];
+        }
+        let reverted = outcome.reverted;
+        with_state(|s| s.return_data = outcome.data);
+        if reverted {
+            Err(ReturnErrorCode::CalleeReverted)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn return_data_size() -> u64 {
+        with_state(|s| s.return_data.len() as u64)
+    }
+
+    pub fn return_data_copy(output: &mut &mut [u8], offset: u32) {
+        with_state(|s| {
+            let start = (offset as usize).min(s.return_data.len());
+            let available = &s.return_data[start..];
+            let len = available.len().min(output.len());
+            output[..len].copy_from_slice(&available[..len]);
+            let out = core::mem::take(output);
+            *output = &mut out[..len];
+        })
+    }
+
+    pub fn return_value(flags: ReturnFlags, return_value: &[u8]) -> ! {
+        // The contract is done; unwinding allocates on the host only
+        COUNTING.set(false);
+        panic::resume_unwind(Box::new(ReturnSignal {
+            reverted: flags.contains(ReturnFlags::REVERT),
+            data: return_value.to_vec(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calls::{self, CallError};
+    use crate::storage;
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn write_then_return() {
+        storage::set_storage(&KEY, b"written");
+        MockApi::deposit_event(&[[1u8; 32]], b"event");
+        MockApi::return_value(ReturnFlags::empty(), b"output");
+    }
+
+    fn write_then_revert() {
+        storage::set_storage(&KEY, b"reverted");
+        MockApi::deposit_event(&[[2u8; 32]], b"event");
+        MockApi::return_value(ReturnFlags::REVERT, b"reason");
+    }
+
+    fn write_then_trap() {
+        storage::set_storage(&KEY, b"trapped");
+        panic!("trap");
+    }
+
+    fn echo_call_data() {
+        let mut input = vec![0u8; MockApi::call_data_size() as usize + 4];
+        MockApi::call_data_copy(&mut input, 0);
+        MockApi::return_value(ReturnFlags::empty(), &input);
+    }
+
+    #[test]
+    fn return_value_unwinds_to_execute() {
+        reset();
+        let out = execute(write_then_return, &[]);
+        assert_eq!(out, Outcome { reverted: false, data: b"output".to_vec() });
+        assert_eq!(storage::get_storage(&KEY).as_deref(), Some(&b"written"[..]));
+        assert_eq!(events(), vec![Event { topics: vec![[1u8; 32]], data: b"event".to_vec() }]);
+    }
+
+    #[test]
+    fn returning_normally_gives_empty_data() {
+        reset();
+        assert_eq!(execute(|| {}, b"ignored"), Outcome { reverted: false, data: Vec::new() });
+    }
+
+    #[test]
+    fn revert_rolls_back_storage_and_events() {
+        reset();
+        execute(write_then_return, &[]);
+        let out = execute(write_then_revert, &[]);
+        assert_eq!(out, Outcome { reverted: true, data: b"reason".to_vec() });
+        assert_eq!(storage::get_storage(&KEY).as_deref(), Some(&b"written"[..]));
+        assert_eq!(events().len(), 1);
+    }
+
+    #[test]
+    fn trap_rolls_back_and_propagates() {
+        reset();
+        execute(write_then_return, &[]);
+        let trapped = panic::catch_unwind(|| execute(write_then_trap, &[]));
+        assert!(trapped.is_err());
+        assert_eq!(storage::get_storage(&KEY).as_deref(), Some(&b"written"[..]));
+    }
+
+    #[test]
+    fn call_data_reads_past_the_end_as_zero() {
+        reset();
+        let out = execute(echo_call_data, &[1, 2, 3]);
+        assert_eq!(out.data, [1, 2, 3, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn environment_and_reset() {
+        reset();
+        set_caller([0x11; 20]);
+        set_now(42);
+        let mut caller = [0u8; 20];
+        MockApi::caller(&mut caller);
+        let mut now = [0u8; 32];
+        MockApi::now(&mut now);
+        assert_eq!(caller, [0x11; 20]);
+        assert_eq!(u64::from_le_bytes(now[..8].try_into().unwrap()), 42);
+
+        set_storage(b"raw", b"value");
+        assert_eq!(storage(b"raw"), Some(b"value".to_vec()));
+        reset();
+        assert_eq!(storage(b"raw"), None);
+    }
+
+    #[test]
+    fn empty_value_deletes_the_entry() {
+        reset();
+        set_storage(b"raw", b"value");
+        MockApi::set_storage(StorageFlags::empty(), b"raw", &[]);
+        assert_eq!(storage(b"raw"), None);
+    }
+
+    #[test]
+    fn calls_reach_registered_contracts() {
+        reset();
+        let callee = [0xaa; 20];
+        register_contract(callee, |input| Outcome { reverted: input == b"fail", data: input.to_vec() });
+
+        assert_eq!(calls::static_call(&callee, b"ping"), Ok(b"ping".to_vec()));
+        assert_eq!(MockApi::return_data_size(), 4);
+        assert_eq!(calls::static_call(&callee, b"fail"), Err(CallError::Reverted(b"fail".to_vec())));
+        // Accounts without code succeed with empty output
+        assert_eq!(calls::static_call(&[0xbb; 20], b"ping"), Ok(Vec::new()));
+        assert_eq!(MockApi::return_data_size(), 0);
+    }
+}
@@ -0,0 +1,50 @@
+//! Proof-of-personhood lookups through the personhood precompile, the same
+//! query the frontend makes in `src/lib/personhood.ts`.
+
+use ethabi::Token;
+
+use crate::abi::selector;
+use crate::calls::{self, CallError};
+
+/// Address of the personhood precompile (`0x…0a010000`)
+pub const PRECOMPILE: [u8; 20] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x0a, 0x01, 0, 0];
+
+/// `"dotns"` right-padded to 32 bytes, the context used by DotNS and the
+/// frontend
+pub const DOTNS_CONTEXT: [u8; 32] = {
+    let mut context = [0u8; 32];
+    context[0] = b'd';
+    context[1] = b'o';
+    context[2] = b't';
+    context[3] = b'n';
+    context[4] = b's';
+    context
+};
+
+// Personhood levels, ordered by strength
+pub const STATUS_NONE: u8 = 0;
+pub const STATUS_LITE: u8 = 1;
+pub const STATUS_FULL: u8 = 2;
+
+const PERSONHOOD_STATUS: [u8; 4] = selector("personhoodStatus(address,bytes32)");
+
+/// Result of `personhoodStatus(account, context)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Personhood {
+    pub status: u8,
+    /// Per-context pseudonym of the person, unlinkable across contexts
+    pub context_alias: [u8; 32],
+}
+
+/// Query the precompile for `account` in `context`.
+pub fn query(account: &[u8; 20], context: &[u8; 32]) -> Result<Personhood, CallError> {
+    let args = [Token::Address((*account).into()), Token::FixedBytes(context.to_vec())];
+    let (status, context_alias) = calls::call_view::<(u8, [u8; 32])>(&PRECOMPILE, PERSONHOOD_STATUS, &args)?;
+    Ok(Personhood { status, context_alias })
+}
+
+/// Personhood level of `account` in `context`; [`STATUS_NONE`] if the
+/// precompile can't be queried.
+pub fn status(account: &[u8; 20], context: &[u8; 32]) -> u8 {
+    query(account, context).map_or(STATUS_NONE, |personhood| personhood.status)
+}
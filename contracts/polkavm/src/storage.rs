@@ -1,23 +1,268 @@
-use uapi::{HostFn, HostFnImpl, StorageFlags};
+//! Binary-safe storage codec shared by all contracts.
+//!
+//! Every value is stored with a 4-byte little-endian length header, so reads
+//! return exactly the bytes written (trailing zeros included) and an empty
+//! value is distinguishable from an absent key. Values larger than one slot
+//! spill into continuation slots at `keccak256(KEY_TAG_CHUNK ++ key ++ index)`:
+//!
+//! ```text
+//! key          → [len: u32 LE][bytes 0..HEAD_CAPACITY]
+//! chunk(key,1) → [next CHUNK_CAPACITY bytes]
+//! chunk(key,2) → ...
+//! ```
+//!
+//! Reads go through a single slot-sized stack buffer and allocate exactly
+//! `len` bytes on the heap.
 
-/// Helper functions for contract storage operations
+use alloc::vec::Vec;
 
-pub fn get_storage(key: &[u8; 32]) -> Option<alloc::vec::Vec<u8>> {
-    let mut buf = [0u8; 4096];
-    let mut output: &mut [u8] = &mut buf;
-    let result = HostFnImpl::get_storage(StorageFlags::empty(), key, &mut output);
-    if result.is_ok() {
-        Some(output.to_vec())
-    } else {
-        None
+#[cfg(not(feature = "mock"))]
+use crate::host::HostFn;
+use crate::host::{Api, StorageFlags};
+use crate::keccak;
+
+/// Largest raw value written to a single storage slot.
+const SLOT_SIZE: usize = 128;
+const HEADER_SIZE: usize = 4;
+const HEAD_CAPACITY: usize = SLOT_SIZE - HEADER_SIZE;
+const CHUNK_CAPACITY: usize = SLOT_SIZE;
+
+/// First byte of every hashed key preimage, so keys derived for different
+/// purposes can never share a preimage.
+pub(crate) const KEY_TAG_CHUNK: u8 = 0x00;
+pub(crate) const KEY_TAG_VALUE: u8 = 0x01;
+pub(crate) const KEY_TAG_MAP: u8 = 0x02;
+pub(crate) const KEY_TAG_VEC: u8 = 0x03;
+pub(crate) const KEY_TAG_VEC_ITEM: u8 = 0x04;
+
+fn chunk_key(key: &[u8; 32], index: u32) -> [u8; 32] {
+    let mut preimage = [0u8; 37];
+    preimage[0] = KEY_TAG_CHUNK;
+    preimage[1..33].copy_from_slice(key);
+    preimage[33..].copy_from_slice(&index.to_le_bytes());
+    keccak::hash(&preimage)
+}
+
+/// Continuation slots needed for a value of `len` bytes.
+fn chunk_count(len: usize) -> u32 {
+    len.saturating_sub(HEAD_CAPACITY).div_ceil(CHUNK_CAPACITY) as u32
+}
+
+/// Read one raw slot into `buf`, returning the stored length.
+fn read_slot(key: &[u8; 32], buf: &mut [u8; SLOT_SIZE]) -> Option<usize> {
+    let mut output: &mut [u8] = buf;
+    Api::get_storage(StorageFlags::empty(), key, &mut output).ok()?;
+    Some(output.len())
+}
+
+fn write_slot(key: &[u8; 32], value: &[u8]) {
+    Api::set_storage(StorageFlags::empty(), key, value);
+}
+
+fn clear_slot(key: &[u8; 32]) {
+    // clear_storage removed in v0.10; set to empty value to delete the entry
+    Api::set_storage(StorageFlags::empty(), key, &[]);
+}
+
+/// Length of the stored value, `None` if absent or not written by this codec.
+fn read_header(key: &[u8; 32], buf: &mut [u8; SLOT_SIZE]) -> Option<usize> {
+    let slot_len = read_slot(key, buf)?;
+    if slot_len < HEADER_SIZE {
+        return None;
     }
+    let mut header = [0u8; HEADER_SIZE];
+    header.copy_from_slice(&buf[..HEADER_SIZE]);
+    Some(u32::from_le_bytes(header) as usize)
 }
 
+/// Exact stored bytes, `None` if absent.
+pub fn get_storage(key: &[u8; 32]) -> Option<Vec<u8>> {
+    let mut buf = [0u8; SLOT_SIZE];
+    let len = read_header(key, &mut buf)?;
+
+    let mut value = Vec::with_capacity(len);
+    let head = len.min(HEAD_CAPACITY);
+    value.extend_from_slice(&buf[HEADER_SIZE..HEADER_SIZE + head]);
+
+    for index in 1..=chunk_count(len) {
+        let take = (len - value.len()).min(CHUNK_CAPACITY);
+        let slot_len = read_slot(&chunk_key(key, index), &mut buf)?;
+        if slot_len < take {
+            return None;
+        }
+        value.extend_from_slice(&buf[..take]);
+    }
+    Some(value)
+}
+
+/// Store `value` byte-exact; an empty value is still present.
 pub fn set_storage(key: &[u8; 32], value: &[u8]) {
-    HostFnImpl::set_storage(StorageFlags::empty(), key, value);
+    let mut buf = [0u8; SLOT_SIZE];
+    let old_chunks = read_header(key, &mut buf).map_or(0, chunk_count);
+
+    let head = value.len().min(HEAD_CAPACITY);
+    buf[..HEADER_SIZE].copy_from_slice(&(value.len() as u32).to_le_bytes());
+    buf[HEADER_SIZE..HEADER_SIZE + head].copy_from_slice(&value[..head]);
+    write_slot(key, &buf[..HEADER_SIZE + head]);
+
+    let new_chunks = chunk_count(value.len());
+    for (index, chunk) in (1..).zip(value[head..].chunks(CHUNK_CAPACITY)) {
+        write_slot(&chunk_key(key, index), chunk);
+    }
+    for index in new_chunks + 1..=old_chunks {
+        clear_slot(&chunk_key(key, index));
+    }
 }
 
+/// Remove the value and its continuation slots.
 pub fn clear_storage(key: &[u8; 32]) {
-    // clear_storage removed in v0.10; set to empty value to delete the entry
-    HostFnImpl::set_storage(StorageFlags::empty(), key, &[]);
+    let mut buf = [0u8; SLOT_SIZE];
+    let Some(len) = read_header(key, &mut buf) else {
+        return;
+    };
+    for index in 1..=chunk_count(len) {
+        clear_slot(&chunk_key(key, index));
+    }
+    clear_slot(key);
+}
+
+pub fn contains(key: &[u8; 32]) -> bool {
+    let mut buf = [0u8; SLOT_SIZE];
+    read_header(key, &mut buf).is_some()
+}
+
+/// Fixed-size value read without touching the heap; `None` if absent or
+/// stored with a different length.
+pub fn get_fixed<const N: usize>(key: &[u8; 32]) -> Option<[u8; N]> {
+    let mut buf = [0u8; SLOT_SIZE];
+    let len = read_header(key, &mut buf)?;
+    if len != N || N > HEAD_CAPACITY {
+        return None;
+    }
+    let mut out = [0u8; N];
+    out.copy_from_slice(&buf[HEADER_SIZE..HEADER_SIZE + N]);
+    Some(out)
+}
+
+/// Little-endian u64, 0 if absent
+pub fn get_u64(key: &[u8; 32]) -> u64 {
+    get_fixed(key).map_or(0, u64::from_le_bytes)
+}
+
+pub fn set_u64(key: &[u8; 32], value: u64) {
+    set_storage(key, &value.to_le_bytes());
+}
+
+/// 20-byte H160 address, `None` if absent
+pub fn get_address(key: &[u8; 32]) -> Option<[u8; 20]> {
+    get_fixed(key)
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    const KEY: [u8; 32] = [9u8; 32];
+
+    /// Every slot holding part of the value at `KEY`, in order
+    fn slots(len: usize) -> Vec<Option<Vec<u8>>> {
+        let mut slots = vec![mock::storage(&KEY)];
+        for index in 1..=chunk_count(len) + 1 {
+            slots.push(mock::storage(&chunk_key(&KEY, index)));
+        }
+        slots
+    }
+
+    fn value(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8 + 1).collect()
+    }
+
+    #[test]
+    fn round_trips_across_chunk_boundaries() {
+        mock::reset();
+        for len in [0, 1, HEAD_CAPACITY - 1, HEAD_CAPACITY, HEAD_CAPACITY + 1, HEAD_CAPACITY + CHUNK_CAPACITY, HEAD_CAPACITY + CHUNK_CAPACITY + 1, 4096] {
+            let value = value(len);
+            set_storage(&KEY, &value);
+            assert_eq!(get_storage(&KEY), Some(value), "length {len}");
+        }
+    }
+
+    #[test]
+    fn values_are_laid_out_in_slots() {
+        mock::reset();
+        let len = HEAD_CAPACITY + CHUNK_CAPACITY + 10;
+        let value = value(len);
+        set_storage(&KEY, &value);
+
+        let slots = slots(len);
+        let head = slots[0].as_ref().unwrap();
+        assert_eq!(head[..HEADER_SIZE], (len as u32).to_le_bytes());
+        assert_eq!(head[HEADER_SIZE..], value[..HEAD_CAPACITY]);
+        assert_eq!(slots[1].as_deref(), Some(&value[HEAD_CAPACITY..HEAD_CAPACITY + CHUNK_CAPACITY]));
+        assert_eq!(slots[2].as_deref(), Some(&value[HEAD_CAPACITY + CHUNK_CAPACITY..]));
+        assert_eq!(slots[3], None);
+    }
+
+    #[test]
+    fn trailing_zero_bytes_survive() {
+        mock::reset();
+        for len in [1, HEAD_CAPACITY, HEAD_CAPACITY + 1, HEAD_CAPACITY + CHUNK_CAPACITY] {
+            let mut value = vec![0u8; len];
+            value[0] = 7;
+            set_storage(&KEY, &value);
+            assert_eq!(get_storage(&KEY), Some(value), "length {len}");
+        }
+    }
+
+    #[test]
+    fn empty_is_not_absent() {
+        mock::reset();
+        assert_eq!(get_storage(&KEY), None);
+        assert!(!contains(&KEY));
+
+        set_storage(&KEY, &[]);
+        assert_eq!(get_storage(&KEY), Some(Vec::new()));
+        assert!(contains(&KEY));
+
+        clear_storage(&KEY);
+        assert_eq!(get_storage(&KEY), None);
+        assert!(!contains(&KEY));
+    }
+
+    #[test]
+    fn shrinking_and_clearing_free_continuation_slots() {
+        mock::reset();
+        let long = HEAD_CAPACITY + 2 * CHUNK_CAPACITY + 1;
+        set_storage(&KEY, &value(long));
+        set_storage(&KEY, &value(HEAD_CAPACITY + 1));
+        assert_eq!(get_storage(&KEY), Some(value(HEAD_CAPACITY + 1)));
+        assert_eq!(slots(long)[2..], [None, None, None]);
+
+        set_storage(&KEY, &value(long));
+        clear_storage(&KEY);
+        assert!(slots(long).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn fixed_reads_require_the_exact_length() {
+        mock::reset();
+        set_u64(&KEY, 0x0102_0304_0506_0708);
+        assert_eq!(get_u64(&KEY), 0x0102_0304_0506_0708);
+        assert_eq!(get_fixed::<4>(&KEY), None);
+        assert_eq!(get_address(&KEY), None);
+
+        set_storage(&KEY, &[0xaa; 20]);
+        assert_eq!(get_address(&KEY), Some([0xaa; 20]));
+        assert_eq!(get_u64(&KEY), 0);
+        assert_eq!(get_u64(&[1u8; 32]), 0);
+    }
+
+    #[test]
+    fn a_missing_chunk_reads_as_absent() {
+        mock::reset();
+        set_storage(&KEY, &value(HEAD_CAPACITY + 1));
+        mock::set_storage(&chunk_key(&KEY, 1), &[]);
+        assert_eq!(get_storage(&KEY), None);
+    }
 }
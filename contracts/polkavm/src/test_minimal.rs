@@ -1,13 +1,18 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "mock"), no_std, no_main)]
 
 extern crate alloc;
 
+use intran3t_polkavm_contracts::abi::selector;
+use intran3t_polkavm_contracts::storage_layout;
+#[cfg(not(feature = "mock"))]
 use polkavm_derive::polkavm_export;
-use uapi::{HostFn, HostFnImpl as api, StorageFlags, ReturnFlags};
+#[cfg(not(feature = "mock"))]
+use uapi::HostFn;
+use intran3t_polkavm_contracts::host::{Api as api, ReturnFlags};
 
 // ============ Runtime support ============
 
+#[cfg(not(feature = "mock"))]
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     unsafe {
@@ -16,53 +21,37 @@ fn panic(_info: &core::panic::PanicInfo) -> ! {
     }
 }
 
+#[cfg(not(feature = "mock"))]
 #[global_allocator]
 static ALLOCATOR: simplealloc::SimpleAlloc<8192> = simplealloc::SimpleAlloc::new();
 
-// ============ Storage key ============
+// ============ Storage ============
 
-fn counter_key() -> [u8; 32] {
-    let mut key = [0u8; 32];
-    key[0] = 1;
-    key
-}
-
-// ============ Helper functions ============
-
-fn get_u64(key: &[u8; 32]) -> u64 {
-    let mut buf = [0u8; 8];
-    api::get_storage(StorageFlags::empty(), key, &mut &mut buf[..]);
-    u64::from_le_bytes(buf)
-}
-
-fn set_u64(key: &[u8; 32], value: u64) {
-    api::set_storage(StorageFlags::empty(), key, &value.to_le_bytes());
+storage_layout! {
+    COUNTER: StorageValue<u64> = 0x01;
 }
 
 // ============ Contract functions ============
 
 // Selector: keccak256("increment()") = 0xd09de08a
-const SELECTOR_INCREMENT: [u8; 4] = [0xd0, 0x9d, 0xe0, 0x8a];
+const SELECTOR_INCREMENT: [u8; 4] = selector("increment()");
 
 // Selector: keccak256("getCount()") = 0xa87d942c
-const SELECTOR_GET_COUNT: [u8; 4] = [0xa8, 0x7d, 0x94, 0x2c];
+const SELECTOR_GET_COUNT: [u8; 4] = selector("getCount()");
 
 fn increment() -> u64 {
-    let key = counter_key();
-    let count = get_u64(&key);
-    let new_count = count + 1;
-    set_u64(&key, new_count);
+    let new_count = COUNTER.get_or_default() + 1;
+    COUNTER.set(&new_count);
     new_count
 }
 
 fn get_count() -> u64 {
-    let key = counter_key();
-    get_u64(&key)
+    COUNTER.get_or_default()
 }
 
 // ============ Dispatcher ============
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn call() {
     let mut input = [0u8; 4];
     api::call_data_copy(&mut input, 0);
@@ -85,9 +74,11 @@ pub extern "C" fn call() {
     api::return_value(ReturnFlags::empty(), &output);
 }
 
-#[polkavm_export]
+#[cfg_attr(not(feature = "mock"), polkavm_export)]
 pub extern "C" fn deploy() {
     // Initialize counter to 0
-    let key = counter_key();
-    set_u64(&key, 0);
+    COUNTER.set(&0);
 }
+
+#[cfg(feature = "mock")]
+fn main() {}